@@ -0,0 +1,329 @@
+use bevy::{app::Events, core::DefaultTaskPoolOptions, prelude::*};
+use hunter_game::{
+    build_app, elapsed, step, AppOptions, DeathCause, DeathEvent, Settings, SimulationStats,
+    Species, SETTINGS_PATH,
+};
+use serde_json::{from_str, Value};
+use std::{
+    collections::VecDeque,
+    env, fs,
+    process::exit,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+const USAGE: &str =
+    "usage: batch <sweep.json> [--settings <settings.json>] [--threads <n>] [--output <results.csv>]";
+const DEFAULT_DURATION: f32 = 300.0;
+
+struct Parameter {
+    pointer: String,
+    values: Vec<f64>,
+}
+
+struct Sweep {
+    duration: f32,
+    seeds: Vec<u64>,
+    parameters: Vec<Parameter>,
+}
+
+impl Sweep {
+    fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        let sweep: Value = from_str(&contents).map_err(|error| format!("{}: {}", path, error))?;
+
+        let seeds = match sweep["seeds"].as_array() {
+            Some(seeds) => seeds.iter().filter_map(Value::as_u64).collect(),
+            None => vec![0],
+        };
+
+        let mut parameters = Vec::new();
+
+        for parameter in sweep["parameters"].as_array().into_iter().flatten() {
+            let pointer = parameter["pointer"]
+                .as_str()
+                .ok_or("every parameter needs a \"pointer\"")?
+                .to_string();
+
+            let values = match parameter["values"].as_array() {
+                Some(values) => values.iter().filter_map(Value::as_f64).collect(),
+                None => range(
+                    parameter["from"].as_f64().unwrap_or(0.0),
+                    parameter["to"].as_f64().unwrap_or(0.0),
+                    parameter["step"].as_f64().unwrap_or(0.0),
+                ),
+            };
+
+            if values.is_empty() {
+                return Err(format!("{} has no values", pointer));
+            }
+
+            parameters.push(Parameter { pointer, values });
+        }
+
+        Ok(Self {
+            duration: sweep["duration"]
+                .as_f64()
+                .map_or(DEFAULT_DURATION, |duration| duration as f32),
+            seeds,
+            parameters,
+        })
+    }
+
+    fn combinations(&self) -> Vec<Vec<f64>> {
+        let mut combinations = vec![Vec::new()];
+
+        for parameter in self.parameters.iter() {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    parameter.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push(*value);
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        combinations
+    }
+}
+
+struct Run {
+    combination: usize,
+    values: Vec<f64>,
+    seed: u64,
+}
+
+#[derive(Default)]
+struct RunResult {
+    survival: [f32; 3],
+    extinctions: [u32; 3],
+    kills: [[u32; 3]; 4],
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let sweep_path = match args.first() {
+        Some(path) if !path.starts_with("--") => path.clone(),
+        _ => fail(USAGE),
+    };
+    let settings_path = arg_value(&args, "--settings").unwrap_or_else(|| SETTINGS_PATH.to_string());
+    let threads = match arg_value(&args, "--threads") {
+        Some(threads) => threads.parse().unwrap_or_else(|_| fail(USAGE)),
+        None => thread::available_parallelism().map_or(1, |threads| threads.get()),
+    };
+
+    let sweep = Sweep::load(&sweep_path).unwrap_or_else(|error| fail(&error));
+    let settings = Settings::load(&settings_path);
+
+    for parameter in sweep.parameters.iter() {
+        if settings.value.pointer(&parameter.pointer).is_none() {
+            fail(&format!(
+                "{} does not exist in {}",
+                parameter.pointer, settings_path
+            ));
+        }
+    }
+
+    let combinations = sweep.combinations();
+    let runs: VecDeque<Run> = combinations
+        .iter()
+        .enumerate()
+        .flat_map(|(combination, values)| {
+            sweep.seeds.iter().map(move |seed| Run {
+                combination,
+                values: values.clone(),
+                seed: *seed,
+            })
+        })
+        .collect();
+    let total = runs.len();
+
+    let pointers: Arc<Vec<String>> = Arc::new(
+        sweep
+            .parameters
+            .iter()
+            .map(|parameter| parameter.pointer.clone())
+            .collect(),
+    );
+    let base = Arc::new(settings.value);
+    let runs = Arc::new(Mutex::new(runs));
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..threads.max(1).min(total) {
+        let base = Arc::clone(&base);
+        let pointers = Arc::clone(&pointers);
+        let runs = Arc::clone(&runs);
+        let sender = sender.clone();
+        let settings_path = settings_path.clone();
+        let duration = sweep.duration;
+
+        thread::spawn(move || loop {
+            let run = match runs.lock().unwrap().pop_front() {
+                Some(run) => run,
+                None => break,
+            };
+
+            let result = simulate(&base, &settings_path, &pointers, &run, duration);
+            sender.send((run.combination, result)).unwrap();
+        });
+    }
+
+    drop(sender);
+
+    let mut results: Vec<Vec<RunResult>> = combinations.iter().map(|_| Vec::new()).collect();
+
+    for (i, (combination, result)) in receiver.iter().enumerate() {
+        eprintln!("finished run {}/{}", i + 1, total);
+        results[combination].push(result);
+    }
+
+    let table = results_table(&pointers, &combinations, &results);
+
+    match arg_value(&args, "--output") {
+        Some(path) => fs::write(&path, table).unwrap_or_else(|error| fail(&error.to_string())),
+        None => print!("{}", table),
+    }
+}
+
+fn simulate(base: &Value, path: &str, pointers: &[String], run: &Run, duration: f32) -> RunResult {
+    let mut value = base.clone();
+
+    for (pointer, parameter) in pointers.iter().zip(run.values.iter()) {
+        if let Some(target) = value.pointer_mut(pointer) {
+            *target = Value::from(*parameter);
+        }
+    }
+
+    value["simulation"]["seed"] = Value::from(run.seed);
+    value["metrics"]["enabled"] = Value::from(false);
+
+    let mut builder = App::build();
+    builder.insert_resource(DefaultTaskPoolOptions::with_num_threads(1));
+    build_app(
+        &mut builder,
+        Settings {
+            value,
+            path: path.to_string(),
+        },
+        AppOptions {
+            headless: true,
+            ..Default::default()
+        },
+    );
+
+    let mut app = builder.app;
+    let mut deaths = app
+        .world
+        .get_resource::<Events<DeathEvent>>()
+        .unwrap()
+        .get_reader();
+
+    let mut result = RunResult::default();
+    let mut alive = [false; 3];
+    let mut extinct_at = [None; 3];
+
+    while elapsed(&app) < duration {
+        step(&mut app);
+
+        let now = elapsed(&app);
+        let events = app.world.get_resource::<Events<DeathEvent>>().unwrap();
+
+        for death in deaths.iter(events) {
+            result.kills[death.species.index()][death.cause.index()] += 1;
+        }
+
+        let stats = app.world.get_resource::<SimulationStats>().unwrap();
+        let populations = [stats.hares, stats.wolves, stats.deer];
+
+        for (i, population) in populations.iter().enumerate() {
+            if *population > 0 {
+                alive[i] = true;
+            } else if alive[i] {
+                alive[i] = false;
+                result.extinctions[i] += 1;
+                extinct_at[i] = extinct_at[i].or(Some(now));
+            }
+        }
+    }
+
+    for (survival, extinct_at) in result.survival.iter_mut().zip(extinct_at.iter()) {
+        *survival = extinct_at.unwrap_or(duration);
+    }
+
+    result
+}
+
+fn results_table(
+    pointers: &[String],
+    combinations: &[Vec<f64>],
+    results: &[Vec<RunResult>],
+) -> String {
+    let mut header: Vec<String> = pointers
+        .iter()
+        .map(|pointer| pointer.trim_start_matches('/').replace('/', "."))
+        .collect();
+    header.push("runs".to_string());
+
+    for species in Species::ALL.iter().skip(1) {
+        header.push(format!("{}_survival", species.name()));
+    }
+    for species in Species::ALL.iter().skip(1) {
+        header.push(format!("{}_extinctions", species.name()));
+    }
+    for species in Species::ALL.iter() {
+        for cause in DeathCause::ALL.iter() {
+            header.push(format!("{}_{}", species.name(), cause.key()));
+        }
+    }
+
+    let mut lines = vec![header.join(",")];
+
+    for (values, runs) in combinations.iter().zip(results.iter()) {
+        let count = runs.len().max(1) as f32;
+        let mut row: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        row.push(runs.len().to_string());
+
+        for i in 0..3 {
+            let survival: f32 = runs.iter().map(|run| run.survival[i]).sum();
+            row.push(format!("{:.2}", survival / count));
+        }
+        for i in 0..3 {
+            let extinctions: u32 = runs.iter().map(|run| run.extinctions[i]).sum();
+            row.push(extinctions.to_string());
+        }
+        for species in 0..Species::ALL.len() {
+            for cause in 0..DeathCause::ALL.len() {
+                let kills: u32 = runs.iter().map(|run| run.kills[species][cause]).sum();
+                row.push(format!("{:.2}", kills as f32 / count));
+            }
+        }
+
+        lines.push(row.join(","));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn range(from: f64, to: f64, step: f64) -> Vec<f64> {
+    if step <= 0.0 || to < from {
+        return vec![from];
+    }
+
+    let count = ((to - from) / step + 1e-9).floor() as usize + 1;
+
+    (0..count)
+        .map(|i| ((from + i as f64 * step) * 1e6).round() / 1e6)
+        .collect()
+}
+
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    args.iter().skip_while(|arg| *arg != name).nth(1).cloned()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1)
+}
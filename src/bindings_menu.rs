@@ -0,0 +1,202 @@
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    controls::{Binding, InputMap, ACTIONS},
+    settings::Settings,
+    FONT_PATH,
+};
+
+#[derive(Default)]
+struct BindingsMenu {
+    open: bool,
+    selected: usize,
+    capturing: bool,
+    status: String,
+}
+
+pub struct BindingsMenuPlugin;
+
+impl Plugin for BindingsMenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<BindingsMenu>()
+            .add_startup_system(bindings_menu_spawn.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                bindings_menu_controls
+                    .system()
+                    .label("bindings_menu_controls")
+                    .after(InputSystem)
+                    .before("actions"),
+            )
+            .add_system(bindings_menu_update.system().label("bindings_menu"));
+    }
+}
+
+struct BindingsMenuText;
+
+fn bindings_menu_spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(160.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(BindingsMenuText);
+}
+
+fn bindings_menu_controls(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    mut menu: ResMut<BindingsMenu>,
+    mut input_map: ResMut<InputMap>,
+    mut settings: ResMut<Settings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        menu.open = !menu.open;
+        menu.capturing = false;
+    }
+
+    if !menu.open {
+        return;
+    }
+
+    let action = ACTIONS[menu.selected];
+
+    if menu.capturing {
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            keyboard_input.reset(KeyCode::Escape);
+            menu.capturing = false;
+            return;
+        }
+
+        let binding = if let Some(key) = keyboard_input.get_just_pressed().next().copied() {
+            keyboard_input.reset(key);
+            Binding::Key(key)
+        } else if let Some(button) = mouse.get_just_pressed().next().copied() {
+            mouse.reset(button);
+            Binding::Mouse(button)
+        } else if let Some(button) = gamepad_buttons.get_just_pressed().next().copied() {
+            gamepad_buttons.reset(button);
+            Binding::Gamepad(button.1)
+        } else {
+            return;
+        };
+
+        menu.capturing = false;
+        menu.status = if Binding::parse(&binding.to_string()).is_some() {
+            input_map.bind(action, binding);
+            settings.value["controls"]["bindings"] = input_map.to_value();
+            format!("Bound {} to {}", binding, action.label())
+        } else {
+            format!("{} cannot be bound", binding)
+        };
+
+        return;
+    }
+
+    let count = ACTIONS.len();
+
+    if keyboard_input.just_pressed(KeyCode::PageUp) {
+        keyboard_input.reset(KeyCode::PageUp);
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keyboard_input.just_pressed(KeyCode::PageDown) {
+        keyboard_input.reset(KeyCode::PageDown);
+        menu.selected = (menu.selected + 1) % count;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        keyboard_input.reset(KeyCode::Return);
+        menu.capturing = true;
+        menu.status.clear();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        keyboard_input.reset(KeyCode::Back);
+        input_map.clear(action);
+        settings.value["controls"]["bindings"] = input_map.to_value();
+        menu.status = format!("Cleared {}", action.label());
+    }
+
+    let control =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+
+    if control && keyboard_input.just_pressed(KeyCode::S) {
+        keyboard_input.reset(KeyCode::S);
+        menu.status = match settings.save() {
+            Ok(()) => format!("Saved to {}", settings.path),
+            Err(error) => format!("Save failed: {}", error),
+        };
+    }
+}
+
+fn bindings_menu_update(
+    menu: Res<BindingsMenu>,
+    input_map: Res<InputMap>,
+    mut query: Query<(&mut Text, &mut Visible), With<BindingsMenuText>>,
+) {
+    for (mut text, mut visible) in query.iter_mut() {
+        visible.is_visible = menu.open;
+
+        if !menu.open {
+            continue;
+        }
+
+        let mut lines = vec!["Controls".to_string()];
+
+        for (i, action) in ACTIONS.iter().enumerate() {
+            let bindings: Vec<String> = input_map
+                .bindings(*action)
+                .iter()
+                .map(|binding| binding.to_string())
+                .collect();
+            let bindings = if bindings.is_empty() {
+                "unbound".to_string()
+            } else {
+                bindings.join(", ")
+            };
+            let marker = if i == menu.selected { ">" } else { " " };
+
+            lines.push(format!("{} {}: {}", marker, action.label(), bindings));
+        }
+
+        if menu.capturing {
+            lines.push(format!(
+                "Press a key, mouse button or gamepad button for {} (Esc - cancel)",
+                ACTIONS[menu.selected].label()
+            ));
+        } else {
+            lines.push(
+                "PgUp/PgDn - action, Enter - add binding, Backspace - clear, Ctrl+S - save"
+                    .to_string(),
+            );
+        }
+
+        if !menu.status.is_empty() {
+            lines.push(menu.status.clone());
+        }
+
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,201 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::OrthographicProjection,
+    transform::TransformSystem,
+    window::{WindowCreated, WindowResized},
+};
+
+use crate::{
+    components::{MainCamera, MousePosition},
+    inspector::Selection,
+    player::Player,
+    FieldSize,
+};
+
+const PIXELS_PER_LINE: f32 = 20.0;
+
+pub struct CameraData {
+    pub dead_zone: Vec2,
+    pub smoothing: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub zoom_speed: f32,
+    pub fit_on_resize: bool,
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            cursor_screen_to_world.system().label("cursor"),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera_follow
+                .system()
+                .label("camera_follow")
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_system(camera_zoom.system().label("camera_zoom"))
+        .add_system(
+            camera_resize
+                .system()
+                .label("camera_resize")
+                .before("camera_zoom"),
+        );
+    }
+}
+
+fn camera_follow(
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+    player_query: Query<&Transform, (With<Player>, Without<MainCamera>)>,
+    followed_query: Query<&Transform, Without<MainCamera>>,
+    windows: Res<Windows>,
+    field_size: Res<FieldSize>,
+    camera_data: Res<CameraData>,
+    selection: Res<Selection>,
+    time: Res<Time>,
+) {
+    let (mut camera_transform, projection) = match camera_query.single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    let followed = selection
+        .entity
+        .filter(|_| selection.follow)
+        .and_then(|entity| followed_query.get(entity).ok());
+
+    let followed_transform = match followed.or_else(|| player_query.single().ok()) {
+        Some(followed_transform) => followed_transform,
+        None => return,
+    };
+
+    let camera = camera_transform.translation.truncate();
+    let followed = followed_transform.translation.truncate();
+    let offset = followed - camera;
+    let half_dead_zone = camera_data.dead_zone / 2.0;
+    let mut target = camera;
+
+    if offset.x > half_dead_zone.x {
+        target.x = followed.x - half_dead_zone.x;
+    } else if offset.x < -half_dead_zone.x {
+        target.x = followed.x + half_dead_zone.x;
+    }
+
+    if offset.y > half_dead_zone.y {
+        target.y = followed.y - half_dead_zone.y;
+    } else if offset.y < -half_dead_zone.y {
+        target.y = followed.y + half_dead_zone.y;
+    }
+
+    if let Some(window) = windows.get_primary() {
+        let half_view = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+        let half_field = Vec2::new(field_size.width, field_size.height) / 2.0;
+        let bounds = (half_field - half_view).max(Vec2::ZERO);
+
+        target = target.max(-bounds).min(bounds);
+    }
+
+    let t = (camera_data.smoothing * time.delta_seconds()).min(1.0);
+    let position = camera.lerp(target, t);
+
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
+
+fn camera_zoom(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    windows: Res<Windows>,
+    field_size: Res<FieldSize>,
+    camera_data: Res<CameraData>,
+) {
+    let scroll: f32 = wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+
+    if scroll == 0.0 {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    if let Ok(mut projection) = query.single_mut() {
+        let max_zoom = camera_data
+            .max_zoom
+            .min(fit_scale(window.width(), window.height(), &field_size))
+            .max(camera_data.min_zoom);
+
+        projection.scale = (projection.scale * (1.0 - camera_data.zoom_speed).powf(scroll))
+            .max(camera_data.min_zoom)
+            .min(max_zoom);
+    }
+}
+
+fn camera_resize(
+    mut resized_events: EventReader<WindowResized>,
+    mut created_events: EventReader<WindowCreated>,
+    mut query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    windows: Res<Windows>,
+    field_size: Res<FieldSize>,
+    camera_data: Res<CameraData>,
+) {
+    let resized = resized_events.iter().count() + created_events.iter().count() > 0;
+
+    if !resized {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return;
+    }
+
+    if let Ok(mut projection) = query.single_mut() {
+        let fit = fit_scale(window.width(), window.height(), &field_size);
+
+        projection.scale = if camera_data.fit_on_resize {
+            fit
+        } else {
+            projection
+                .scale
+                .min(fit.min(camera_data.max_zoom))
+                .max(camera_data.min_zoom)
+        };
+    }
+}
+
+fn fit_scale(width: f32, height: f32, field_size: &FieldSize) -> f32 {
+    (field_size.width / width).max(field_size.height / height)
+}
+
+fn cursor_screen_to_world(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    query: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    let window = windows.get_primary().unwrap();
+
+    if let Some(pos) = window.cursor_position() {
+        let size = Vec2::new(window.width(), window.height());
+        let (camera_transform, projection) = query.single().unwrap();
+        let p = (pos - size / 2.0) * projection.scale;
+        let pos_wld = camera_transform.compute_matrix() * p.extend(0.0).extend(1.0);
+        commands.insert_resource(MousePosition::new(pos_wld.x, pos_wld.y));
+    }
+}
@@ -15,12 +15,23 @@ impl MovementSpeed {
 
 pub(crate) struct MainCamera;
 
+pub(crate) struct Hidden {
+    pub last_threat: f32,
+}
+
+#[derive(Default)]
 pub(crate) struct Materials {
     pub player_material: Handle<ColorMaterial>,
     pub hare_material: Handle<ColorMaterial>,
     pub wolf_material: Handle<ColorMaterial>,
     pub deer_material: Handle<ColorMaterial>,
     pub bullet_material: Handle<ColorMaterial>,
+    pub burrow_material: Handle<ColorMaterial>,
+    pub den_material: Handle<ColorMaterial>,
+    pub wall_material: Handle<ColorMaterial>,
+    pub obstacle_material: Handle<ColorMaterial>,
+    pub food_material: Handle<ColorMaterial>,
+    pub region_material: Handle<ColorMaterial>,
 }
 
 pub(crate) struct MousePosition {
@@ -42,3 +53,18 @@ impl Default for MousePosition {
         }
     }
 }
+
+pub(crate) struct Health {
+    pub value: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { value: max, max }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.value / self.max
+    }
+}
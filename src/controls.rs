@@ -0,0 +1,635 @@
+use bevy::{input::InputSystem, prelude::*};
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::geometry::normalize_or_zero;
+
+pub(crate) const ACTIONS: [Action; 12] = [
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Sprint,
+    Action::Fire,
+    Action::Pause,
+    Action::Step,
+    Action::SlowDown,
+    Action::SpeedUp,
+    Action::ToggleDebug,
+];
+
+const DEFAULT_BINDINGS: [(Action, &[&str]); 12] = [
+    (Action::MoveUp, &["W", "Gamepad:DPadUp"]),
+    (Action::MoveDown, &["S", "Gamepad:DPadDown"]),
+    (Action::MoveLeft, &["A", "Gamepad:DPadLeft"]),
+    (Action::MoveRight, &["D", "Gamepad:DPadRight"]),
+    (Action::Sprint, &["LShift", "Gamepad:LeftThumb"]),
+    (Action::Fire, &["Mouse:Left", "Gamepad:RightTrigger2"]),
+    (Action::Pause, &["P", "Gamepad:Start"]),
+    (Action::Step, &["Period"]),
+    (Action::SlowDown, &["LBracket"]),
+    (Action::SpeedUp, &["RBracket"]),
+    (Action::ToggleDebug, &["F1"]),
+];
+
+const KEYS: &[KeyCode] = &[
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Return,
+    KeyCode::Escape,
+    KeyCode::Back,
+    KeyCode::Tab,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::Minus,
+    KeyCode::Equals,
+    KeyCode::LBracket,
+    KeyCode::RBracket,
+    KeyCode::Grave,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+];
+
+const GAMEPAD_BUTTONS: &[GamepadButtonType] = &[
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::C,
+    GamepadButtonType::Z,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::Mode,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+const MOUSE_PREFIX: &str = "Mouse:";
+const GAMEPAD_PREFIX: &str = "Gamepad:";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Sprint,
+    Fire,
+    Pause,
+    Step,
+    SlowDown,
+    SpeedUp,
+    ToggleDebug,
+}
+
+impl Action {
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Sprint => "sprint",
+            Action::Fire => "fire",
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::SlowDown => "slow_down",
+            Action::SpeedUp => "speed_up",
+            Action::ToggleDebug => "toggle_debug",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Sprint => "Sprint",
+            Action::Fire => "Fire",
+            Action::Pause => "Pause",
+            Action::Step => "Step",
+            Action::SlowDown => "Slow down",
+            Action::SpeedUp => "Speed up",
+            Action::ToggleDebug => "Toggle debug",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix(MOUSE_PREFIX) {
+            let button = match button {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                other => MouseButton::Other(other.parse().ok()?),
+            };
+
+            return Some(Binding::Mouse(button));
+        }
+
+        if let Some(button) = name.strip_prefix(GAMEPAD_PREFIX) {
+            return GAMEPAD_BUTTONS
+                .iter()
+                .find(|b| format!("{:?}", b) == button)
+                .map(|b| Binding::Gamepad(*b));
+        }
+
+        KEYS.iter()
+            .find(|key| format!("{:?}", key) == name)
+            .map(|key| Binding::Key(*key))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(MouseButton::Other(button)) => write!(f, "{}{}", MOUSE_PREFIX, button),
+            Binding::Mouse(button) => write!(f, "{}{:?}", MOUSE_PREFIX, button),
+            Binding::Gamepad(button) => write!(f, "{}{:?}", GAMEPAD_PREFIX, button),
+        }
+    }
+}
+
+pub(crate) struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl InputMap {
+    pub fn from_value(value: &Value) -> Self {
+        let mut bindings = HashMap::new();
+
+        for (action, defaults) in DEFAULT_BINDINGS.iter() {
+            let names: Vec<&str> = match value[action.key()].as_array() {
+                Some(names) => names.iter().filter_map(Value::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+
+            let mut parsed = Vec::new();
+
+            for name in names {
+                match Binding::parse(name) {
+                    Some(binding) if !parsed.contains(&binding) => parsed.push(binding),
+                    Some(_) => {}
+                    None => warn!("Unknown binding {} for {}", name, action.key()),
+                }
+            }
+
+            bindings.insert(*action, parsed);
+        }
+
+        Self { bindings }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let map: Map<String, Value> = ACTIONS
+            .iter()
+            .map(|action| {
+                let names = self
+                    .bindings(*action)
+                    .iter()
+                    .map(|binding| Value::from(binding.to_string()))
+                    .collect();
+
+                (action.key().to_string(), Value::Array(names))
+            })
+            .collect();
+
+        Value::Object(map)
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        for bindings in self.bindings.values_mut() {
+            bindings.retain(|b| *b != binding);
+        }
+
+        self.bindings.entry(action).or_default().push(binding);
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.entry(action).or_default().clear();
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::from_value(&Value::Null)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+}
+
+pub(crate) struct ControlsData {
+    pub stick_dead_zone: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct PlayerInput {
+    pub movement: Vec2,
+    pub fire: bool,
+}
+
+#[derive(Default)]
+struct ConnectedGamepads {
+    value: Vec<Gamepad>,
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<InputMap>()
+            .init_resource::<ActionState>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<ConnectedGamepads>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_connections
+                    .system()
+                    .label("gamepad_connections")
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                action_input.system().label("actions").after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                player_input
+                    .system()
+                    .label("player_input")
+                    .after("actions")
+                    .after("gamepad_connections"),
+            );
+    }
+}
+
+fn gamepad_connections(
+    mut gamepads: ResMut<ConnectedGamepads>,
+    mut events: EventReader<GamepadEvent>,
+) {
+    for GamepadEvent(gamepad, event) in events.iter() {
+        match event {
+            GamepadEventType::Connected => {
+                if !gamepads.value.contains(gamepad) {
+                    gamepads.value.push(*gamepad);
+                }
+            }
+            GamepadEventType::Disconnected => gamepads.value.retain(|g| g != gamepad),
+            _ => {}
+        }
+    }
+}
+
+fn action_input(
+    mut actions: ResMut<ActionState>,
+    input_map: Res<InputMap>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    let state = |binding: &Binding| match binding {
+        Binding::Key(key) => (
+            keyboard_input.pressed(*key),
+            keyboard_input.just_pressed(*key),
+            keyboard_input.just_released(*key),
+        ),
+        Binding::Mouse(button) => (
+            mouse.pressed(*button),
+            mouse.just_pressed(*button),
+            mouse.just_released(*button),
+        ),
+        Binding::Gamepad(button) => (
+            any_gamepad(gamepad_buttons.get_pressed(), *button),
+            any_gamepad(gamepad_buttons.get_just_pressed(), *button),
+            any_gamepad(gamepad_buttons.get_just_released(), *button),
+        ),
+    };
+
+    let mut next = ActionState::default();
+
+    for action in ACTIONS.iter() {
+        let (mut pressed, mut just_pressed, mut just_released) = (false, false, false);
+
+        for binding in input_map.bindings(*action) {
+            let (is_pressed, is_just_pressed, is_just_released) = state(binding);
+            pressed |= is_pressed;
+            just_pressed |= is_just_pressed;
+            just_released |= is_just_released;
+        }
+
+        if pressed {
+            next.pressed.insert(*action);
+        }
+        if just_pressed && !actions.pressed(*action) {
+            next.just_pressed.insert(*action);
+        }
+        if just_released && !pressed {
+            next.just_released.insert(*action);
+        }
+    }
+
+    *actions = next;
+}
+
+fn any_gamepad<'a>(
+    mut buttons: impl Iterator<Item = &'a GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    buttons.any(|GamepadButton(_, t)| *t == button_type)
+}
+
+fn player_input(
+    mut input: ResMut<PlayerInput>,
+    actions: Res<ActionState>,
+    controls_data: Res<ControlsData>,
+    gamepads: Res<ConnectedGamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    let digital = Vec2::new(
+        axis(
+            actions.pressed(Action::MoveLeft),
+            actions.pressed(Action::MoveRight),
+        ),
+        axis(
+            actions.pressed(Action::MoveDown),
+            actions.pressed(Action::MoveUp),
+        ),
+    );
+
+    let mut movement = normalize_or_zero(digital);
+
+    for gamepad in gamepads.value.iter() {
+        let stick = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis(*gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+
+        movement += apply_dead_zone(
+            Vec2::new(
+                stick(GamepadAxisType::LeftStickX),
+                stick(GamepadAxisType::LeftStickY),
+            ),
+            controls_data.stick_dead_zone,
+        );
+    }
+
+    input.movement = clamp_length(movement, 1.0);
+    input.fire |= actions.just_released(Action::Fire);
+}
+
+fn axis(negative: bool, positive: bool) -> f32 {
+    positive as i32 as f32 - negative as i32 as f32
+}
+
+fn apply_dead_zone(stick: Vec2, dead_zone: f32) -> Vec2 {
+    let length = stick.length().min(1.0);
+
+    if length <= dead_zone || dead_zone >= 1.0 {
+        return Vec2::ZERO;
+    }
+
+    normalize_or_zero(stick) * (length - dead_zone) / (1.0 - dead_zone)
+}
+
+fn clamp_length(vec: Vec2, max: f32) -> Vec2 {
+    if vec.length() > max {
+        normalize_or_zero(vec) * max
+    } else {
+        vec
+    }
+}
+
+pub(crate) fn approach(current: Vec2, target: Vec2, max_delta: f32) -> Vec2 {
+    let delta = target - current;
+
+    if delta.length() <= max_delta {
+        target
+    } else {
+        current + normalize_or_zero(delta) * max_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    #[test]
+    fn opposing_keys_cancel() {
+        assert_eq!(axis(true, true), 0.0);
+        assert_eq!(axis(true, false), -1.0);
+        assert_eq!(axis(false, true), 1.0);
+        assert_eq!(axis(false, false), 0.0);
+    }
+
+    #[test]
+    fn diagonal_movement_is_not_faster() {
+        let diagonal = normalize_or_zero(Vec2::new(axis(false, true), axis(false, true)));
+
+        assert!((diagonal.length() - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn dead_zone_rescales_stick() {
+        assert_eq!(apply_dead_zone(Vec2::new(0.1, 0.0), 0.2), Vec2::ZERO);
+        assert!((apply_dead_zone(Vec2::new(0.6, 0.0), 0.2).x - 0.5).abs() < TOLERANCE);
+        assert!((apply_dead_zone(Vec2::new(1.0, 1.0), 0.2).length() - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn combined_input_is_clamped() {
+        let combined = clamp_length(Vec2::new(1.0, 0.0) + Vec2::new(0.0, 1.0), 1.0);
+
+        assert!((combined.length() - 1.0).abs() < TOLERANCE);
+        assert_eq!(clamp_length(Vec2::new(0.3, 0.4), 1.0), Vec2::new(0.3, 0.4));
+    }
+
+    #[test]
+    fn bindings_round_trip_through_names() {
+        for name in [
+            "W",
+            "LShift",
+            "F1",
+            "Mouse:Left",
+            "Mouse:4",
+            "Gamepad:RightTrigger2",
+        ] {
+            assert_eq!(Binding::parse(name).unwrap().to_string(), name);
+        }
+
+        assert_eq!(Binding::parse("Gamepad:Nope"), None);
+        assert_eq!(Binding::parse("Nope"), None);
+    }
+
+    #[test]
+    fn missing_actions_use_default_bindings() {
+        let map = InputMap::from_value(&json!({ "fire": ["Space", "Mouse:Right"] }));
+
+        assert_eq!(
+            map.bindings(Action::Fire),
+            &[
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Right)
+            ]
+        );
+        assert_eq!(
+            map.bindings(Action::MoveUp),
+            InputMap::default().bindings(Action::MoveUp)
+        );
+        assert_eq!(
+            InputMap::from_value(&map.to_value()).to_value(),
+            map.to_value()
+        );
+    }
+
+    #[test]
+    fn binding_moves_between_actions() {
+        let mut map = InputMap::default();
+        map.bind(Action::Fire, Binding::Key(KeyCode::P));
+
+        assert!(map
+            .bindings(Action::Fire)
+            .contains(&Binding::Key(KeyCode::P)));
+        assert!(!map
+            .bindings(Action::Pause)
+            .contains(&Binding::Key(KeyCode::P)));
+
+        map.clear(Action::Fire);
+        assert!(map.bindings(Action::Fire).is_empty());
+    }
+
+    #[test]
+    fn approach_never_overshoots() {
+        let target = Vec2::new(10.0, 0.0);
+        let mut current = Vec2::ZERO;
+
+        for _ in 0..3 {
+            current = approach(current, target, 4.0);
+        }
+
+        assert_eq!(current, target);
+        assert_eq!(approach(Vec2::ZERO, target, 4.0), Vec2::new(4.0, 0.0));
+    }
+}
@@ -0,0 +1,335 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use std::f32::consts::PI;
+
+use crate::{
+    controls::{Action, ActionState},
+    deer::{Deer, DeerData},
+    hare::{Hare, HareData},
+    perception::Perception,
+    player::{Bullet, BulletData, Player, PlayerData},
+    steering::{Physics, SteeringDebug, STEERING_KINDS},
+    targeting::Target,
+    utils::dist,
+    wolf::{Wolf, WolfData},
+    Walls,
+};
+
+const LAYERS: usize = 7;
+const LAYER_KEYS: [KeyCode; LAYERS] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+];
+
+const VELOCITY: usize = 0;
+const FORCES: usize = 1;
+const WANDER: usize = 2;
+const PERCEPTION: usize = 3;
+const WALLS: usize = 4;
+const AABBS: usize = 5;
+const TARGETS: usize = 6;
+
+const LINE_WIDTH: f32 = 1.5;
+const CIRCLE_SEGMENTS: u32 = 24;
+const DEBUG_Z: f32 = 10.0;
+const VELOCITY_SCALE: f32 = 20.0;
+const FORCE_SCALE: f32 = 200.0;
+
+pub struct DebugOverlay {
+    pub enabled: bool,
+    pub layers: [bool; LAYERS],
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            layers: [true; LAYERS],
+        }
+    }
+}
+
+struct DebugMaterials {
+    layers: [Handle<ColorMaterial>; LAYERS],
+    forces: [Handle<ColorMaterial>; STEERING_KINDS],
+}
+
+struct DebugShape;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<DebugOverlay>()
+            .add_startup_system(debug_materials.system())
+            .add_system(debug_controls.system().label("debug_controls"))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                debug_draw
+                    .system()
+                    .label("debug_draw")
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn debug_materials(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let mut material = |r: f32, g: f32, b: f32| materials.add(Color::rgb(r, g, b).into());
+
+    commands.insert_resource(DebugMaterials {
+        layers: [
+            material(1.0, 1.0, 1.0),
+            material(1.0, 1.0, 1.0),
+            material(1.0, 0.9, 0.2),
+            material(0.2, 0.9, 0.9),
+            material(1.0, 0.2, 0.2),
+            material(0.9, 0.2, 0.9),
+            material(1.0, 0.5, 0.0),
+        ],
+        forces: [
+            material(1.0, 0.9, 0.2),
+            material(0.2, 0.6, 1.0),
+            material(0.6, 1.0, 0.6),
+            material(1.0, 0.3, 0.3),
+            material(0.9, 0.5, 0.9),
+            material(0.5, 0.9, 0.9),
+            material(1.0, 0.6, 0.2),
+            material(1.0, 0.1, 0.1),
+        ],
+    });
+}
+
+fn debug_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if actions.just_pressed(Action::ToggleDebug) {
+        overlay.enabled = !overlay.enabled;
+    }
+
+    for (layer, key) in LAYER_KEYS.iter().enumerate() {
+        if keyboard_input.just_pressed(*key) {
+            overlay.layers[layer] = !overlay.layers[layer];
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn debug_draw(
+    mut commands: Commands,
+    shape_query: Query<Entity, With<DebugShape>>,
+    steering_query: Query<(&Transform, &Physics, &SteeringDebug)>,
+    perception_query: Query<(&Transform, &Perception)>,
+    body_query: Query<(
+        &Transform,
+        Option<&Player>,
+        Option<&Hare>,
+        Option<&Wolf>,
+        Option<&Deer>,
+        Option<&Bullet>,
+    )>,
+    target_query: Query<(&Transform, &Target), With<Wolf>>,
+    transform_query: Query<&Transform>,
+    sizes: (
+        Res<PlayerData>,
+        Res<HareData>,
+        Res<WolfData>,
+        Res<DeerData>,
+        Res<BulletData>,
+    ),
+    overlay: Res<DebugOverlay>,
+    materials: Res<DebugMaterials>,
+    walls: Res<Walls>,
+) {
+    for shape in shape_query.iter() {
+        commands.entity(shape).despawn();
+    }
+
+    if !overlay.enabled {
+        return;
+    }
+
+    let layer = |index: usize| overlay.layers[index];
+
+    for (transform, physics, steering) in steering_query.iter() {
+        let position = transform.translation;
+
+        if layer(VELOCITY) {
+            let material = &materials.layers[VELOCITY];
+            line(
+                &mut commands,
+                material,
+                position,
+                position + physics.velocity * VELOCITY_SCALE,
+            );
+        }
+
+        if layer(FORCES) {
+            for (kind, force) in steering.applied.iter().enumerate() {
+                if *force == Vec3::ZERO {
+                    continue;
+                }
+
+                let material = &materials.forces[kind];
+                line(
+                    &mut commands,
+                    material,
+                    position,
+                    position + *force * FORCE_SCALE,
+                );
+            }
+        }
+
+        if layer(WANDER) {
+            if let Some((center, radius, target)) = steering.wander {
+                let material = &materials.layers[WANDER];
+                circle(&mut commands, material, center, radius);
+                line(&mut commands, material, position, center);
+                line(&mut commands, material, center, target);
+            }
+        }
+
+        if layer(WALLS) {
+            if let Some((from, to)) = steering.wall_ray {
+                line(&mut commands, &materials.layers[WALLS], from, to);
+            }
+        }
+    }
+
+    if layer(PERCEPTION) {
+        let material = &materials.layers[PERCEPTION];
+
+        for (transform, perception) in perception_query.iter() {
+            let position = transform.translation;
+            let facing = transform.local_y();
+            let half_fov = perception.field_of_view / 2.0;
+
+            circle(&mut commands, material, position, perception.hearing_radius);
+
+            for angle in [-half_fov, half_fov].iter() {
+                let edge = Quat::from_rotation_z(*angle).mul_vec3(facing) * perception.range;
+                line(&mut commands, material, position, position + edge);
+            }
+        }
+    }
+
+    if layer(WALLS) {
+        for wall in walls.value.iter() {
+            line(
+                &mut commands,
+                &materials.layers[WALLS],
+                wall.point_a,
+                wall.point_b,
+            );
+        }
+
+        for obstacle in walls.obstacles.iter() {
+            circle(
+                &mut commands,
+                &materials.layers[WALLS],
+                obstacle.position,
+                obstacle.radius,
+            );
+        }
+    }
+
+    if layer(AABBS) {
+        let (player_data, hare_data, wolf_data, deer_data, bullet_data) = &sizes;
+        let material = &materials.layers[AABBS];
+
+        for (transform, player, hare, wolf, deer, bullet) in body_query.iter() {
+            let size = if player.is_some() {
+                Vec2::new(player_data.width, player_data.height)
+            } else if hare.is_some() {
+                Vec2::new(hare_data.width, hare_data.height)
+            } else if wolf.is_some() {
+                Vec2::new(wolf_data.width, wolf_data.height)
+            } else if deer.is_some() {
+                Vec2::new(deer_data.width, deer_data.height)
+            } else if bullet.is_some() {
+                Vec2::new(bullet_data.width, bullet_data.height)
+            } else {
+                continue;
+            };
+
+            rectangle(&mut commands, material, transform.translation, size);
+        }
+    }
+
+    if layer(TARGETS) {
+        let material = &materials.layers[TARGETS];
+
+        for (transform, target) in target_query.iter() {
+            if let Some(target_transform) = target
+                .entity
+                .and_then(|entity| transform_query.get(entity).ok())
+            {
+                line(
+                    &mut commands,
+                    material,
+                    transform.translation,
+                    target_transform.translation,
+                );
+            }
+        }
+    }
+}
+
+fn line(commands: &mut Commands, material: &Handle<ColorMaterial>, from: Vec3, to: Vec3) {
+    let length = dist(from, to);
+
+    if length == 0.0 || length.is_nan() {
+        return;
+    }
+
+    let middle = (from + to) / 2.0;
+    let angle = (to.y - from.y).atan2(to.x - from.x);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: material.clone(),
+            sprite: Sprite::new(Vec2::new(length, LINE_WIDTH)),
+            transform: Transform {
+                translation: Vec3::new(middle.x, middle.y, DEBUG_Z),
+                rotation: Quat::from_rotation_z(angle),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(DebugShape);
+}
+
+fn circle(commands: &mut Commands, material: &Handle<ColorMaterial>, center: Vec3, radius: f32) {
+    let point = |i: u32| {
+        let angle = i as f32 * 2.0 * PI / CIRCLE_SEGMENTS as f32;
+        center + Vec3::new(angle.cos(), angle.sin(), 0.0) * radius
+    };
+
+    for i in 0..CIRCLE_SEGMENTS {
+        line(commands, material, point(i), point(i + 1));
+    }
+}
+
+fn rectangle(commands: &mut Commands, material: &Handle<ColorMaterial>, center: Vec3, size: Vec2) {
+    let half = size / 2.0;
+    let corners = [
+        center + Vec3::new(-half.x, -half.y, 0.0),
+        center + Vec3::new(half.x, -half.y, 0.0),
+        center + Vec3::new(half.x, half.y, 0.0),
+        center + Vec3::new(-half.x, half.y, 0.0),
+    ];
+
+    for i in 0..corners.len() {
+        line(
+            commands,
+            material,
+            corners[i],
+            corners[(i + 1) % corners.len()],
+        );
+    }
+}
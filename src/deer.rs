@@ -1,23 +1,36 @@
-use std::{f32::consts::PI};
+use std::{collections::HashMap, f32::consts::PI};
 
 use bevy::{
-    math::{Quat, Vec3, Vec2},
+    math::{Quat, Vec2, Vec3},
     prelude::{
-        AppBuilder, Commands, IntoSystem, ParallelSystemDescriptorCoercion, Plugin, Query, Res,
-        ResMut, SpriteBundle, Transform, With, Without, Entity,
-    }, sprite::collide_aabb::collide, core::Time,
+        AppBuilder, Commands, Entity, EventWriter, IntoSystem, ParallelSystemDescriptorCoercion,
+        Plugin, Query, Res, ResMut, SpriteBundle, Transform, With, Without, World,
+    },
+    sprite::collide_aabb::collide,
 };
 use rand::Rng;
+use serde_json::{json, Value};
 
 use crate::{
-    components::{Materials, MovementSpeed, Prey, Threat},
+    components::{Health, Materials, MovementSpeed, Prey, Threat},
     hare::Hare,
+    map::{FoodPatch, MapLayout},
+    perception::{Perception, PerceptionData},
+    player::{Bullet, BulletData},
+    settings::{get_f32, get_u32},
+    simulation::{SimRng, SimulationClock, SIMULATION},
+    snapshot::{
+        entity_value, get_health, get_perception, get_physics, get_transform, get_vec3,
+        health_value, perception_value, physics_value, transform_value, vec3_value, EntityMap,
+    },
+    stats::{BirthEvent, DeathCause, DeathEvent, Species},
     steering::{
-        evade, flee, wander, EvadeData, EvadeWallsData, FleeData, FlockingData, Physics, WanderData,
+        evade, flee, seek, wander, EvadeData, EvadeWallsData, FleeData, FlockingData, Physics,
+        SeekData, SteeringDebug, SteeringKind, WanderData,
     },
-    utils::{dist, limit, line_line_intersection, set_mag},
-    wolf::{Wolf, WolfData, WolfBehavior},
-    FieldSize, Walls, TIME_STEP, player::{Bullet, BulletData},
+    utils::{dist, limit, set_mag},
+    wolf::{Wolf, WolfBehavior, WolfData},
+    FieldSize, Walls, TIME_STEP,
 };
 
 pub(crate) struct DeerData {
@@ -26,7 +39,12 @@ pub(crate) struct DeerData {
     pub width: f32,
     pub height: f32,
     pub max_number: u32,
+    pub health: f32,
     pub group_number: u32,
+    pub min_group_size: u32,
+    pub group_spread: f32,
+    pub spawn_margin: f32,
+    pub perception: PerceptionData,
 }
 
 pub struct DeerSteeringData {
@@ -37,74 +55,169 @@ pub struct DeerSteeringData {
     pub separation: FlockingData,
     pub alignment: FlockingData,
     pub cohesion: FlockingData,
+    pub graze: SeekData,
+    pub herd: HerdData,
+    pub alarm: AlarmData,
+}
+
+pub struct HerdData {
+    pub follow_weight: f32,
+    pub follow_distance: f32,
+    pub merge_radius: f32,
+    pub split_radius: f32,
+}
+
+pub struct AlarmData {
+    pub radius: f32,
+    pub delay: f32,
+    pub decay: f32,
+    pub speed_boost: f32,
+    pub separation_multiplier: f32,
+    pub flee_weight: f32,
 }
 
 struct Behavior {
     force: Vec3,
 }
 
-struct Deer;
+struct Alarm {
+    panic_until: f32,
+    pending_at: Option<f32>,
+    origin: Vec3,
+}
 
-struct GroupID {
-    value: u32,
+impl Alarm {
+    fn is_panicking(&self, now: f32) -> bool {
+        self.panic_until > now
+    }
+}
+
+pub(crate) struct Deer;
+
+pub(crate) struct GroupID {
+    pub value: u32,
 }
 
 struct DeerGroup {
     id: u32,
     count: u32,
+    leader: Option<Entity>,
 }
 
 struct DeerGroups {
     groups: Vec<DeerGroup>,
+    spawned: u32,
+}
+
+impl Default for DeerGroups {
+    fn default() -> Self {
+        Self {
+            groups: Vec::new(),
+            spawned: 0,
+        }
+    }
 }
 
 pub struct DeerPlugin;
 
 impl Plugin for DeerPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.insert_resource(DeerGroups { groups: Vec::new() })
-            .add_system(deer_spawn.system().label("deer_spawn"))
-            .add_system(
+        app.insert_resource(DeerGroups::default())
+            .add_system_to_stage(
+                SIMULATION,
+                deer_spawn.system().label("deer_spawn").after("wolf_wander"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                deer_regroup
+                    .system()
+                    .label("deer_regroup")
+                    .after("deer_spawn"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 deer_wander
                     .system()
                     .label("deer_wander")
                     .after("deer_spawn")
+                    .after("deer_regroup")
                     .before("deer_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
+                deer_follow_leader
+                    .system()
+                    .label("deer_follow_leader")
+                    .after("deer_regroup")
+                    .before("deer_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 deer_flee
                     .system()
                     .label("deer_flee")
                     .before("deer_move")
-                    .after("deer_spawn"),
+                    .after("deer_spawn")
+                    .after("perception"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
+                deer_alarm
+                    .system()
+                    .label("deer_alarm")
+                    .after("perception")
+                    .before("deer_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 deer_alignment
                     .system()
                     .label("deer_alignment")
                     .before("deer_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
                 deer_cohesion
                     .system()
                     .label("deer_cohesion")
                     .before("deer_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
                 deer_separation
                     .system()
                     .label("deer_separation")
                     .before("deer_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
                 deer_evade_walls
                     .system()
                     .label("deer_evade_walls")
                     .before("deer_move"),
             )
-            .add_system(deer_evade.system().label("deer_evade").before("deer_move"))
-            .add_system(deer_move.system().label("deer_move").after("deer_spawn"))
-            .add_system(deer_die.system().label("deer_die"));
+            .add_system_to_stage(
+                SIMULATION,
+                deer_graze
+                    .system()
+                    .label("deer_graze")
+                    .after("perception")
+                    .after("deer_alarm")
+                    .before("deer_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                deer_evade
+                    .system()
+                    .label("deer_evade")
+                    .after("perception")
+                    .before("deer_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                deer_move.system().label("deer_move").after("deer_spawn"),
+            )
+            .add_system_to_stage(SIMULATION, deer_die.system().label("deer_die"));
     }
 }
 
@@ -114,29 +227,23 @@ fn deer_spawn(
     mut deer_groups: ResMut<DeerGroups>,
     filed_size: Res<FieldSize>,
     settings: Res<DeerData>,
+    layout: Res<MapLayout>,
+    mut rng: ResMut<SimRng>,
+    mut births: EventWriter<BirthEvent>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
-        let mut rng = rand::thread_rng();
-        let w_span = filed_size.width / 2.0 - 60.0;
-        let h_span = filed_size.height / 2.0 - 60.0;
-
-        let x = rng.gen_range(-w_span..w_span) as f32;
-        let y = rng.gen_range(-h_span..h_span) as f32;
+    if deer_groups.spawned < settings.group_number {
+        let Vec3 { x, y, .. } =
+            layout.spawn_point(Species::Deer, &filed_size, settings.spawn_margin, &mut *rng);
 
-        let deer_count = rng.gen_range(3..settings.max_number);
+        let deer_count = rng.gen_range(settings.min_group_size..settings.max_number);
         let id = rng.gen();
-        let group = DeerGroup {
-            count: deer_count,
-            id,
-        };
-
-        deer_groups.groups.push(group);
+        let mut leader = None;
 
         for _ in 0..deer_count {
-            let x_offset = rng.gen_range(-30.0..30.0) as f32;
-            let y_offset = rng.gen_range(-30.0..30.0) as f32;
+            let x_offset = rng.gen_range(-settings.group_spread..settings.group_spread) as f32;
+            let y_offset = rng.gen_range(-settings.group_spread..settings.group_spread) as f32;
 
-            commands
+            let deer = commands
                 .spawn_bundle(SpriteBundle {
                     material: materials.deer_material.clone(),
                     transform: Transform {
@@ -154,22 +261,55 @@ fn deer_spawn(
                     acceleration: Vec3::default(),
                     wander_theta: PI / 2.0,
                 })
+                .insert(SteeringDebug::default())
                 .insert(Behavior { force: Vec3::ZERO })
-                .insert(GroupID { value: id });
+                .insert(Alarm {
+                    panic_until: 0.0,
+                    pending_at: None,
+                    origin: Vec3::ZERO,
+                })
+                .insert(GroupID { value: id })
+                .insert(Health::new(settings.health))
+                .insert(Perception::new(&settings.perception))
+                .id();
+
+            births.send(BirthEvent {
+                species: Species::Deer,
+            });
+
+            if leader.is_none() {
+                leader = Some(deer);
+            }
         }
+
+        deer_groups.groups.push(DeerGroup {
+            id,
+            count: deer_count,
+            leader,
+        });
+        deer_groups.spawned += 1;
     }
 }
 
 fn deer_move(
-    mut query: Query<(&mut Transform, &mut Physics, &mut Behavior, &MovementSpeed), With<Deer>>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Physics,
+            &mut Behavior,
+            &mut SteeringDebug,
+            &MovementSpeed,
+        ),
+        With<Deer>,
+    >,
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (mut transform, mut physics, mut behavior, speed) in query.iter_mut() {
+    for (mut transform, mut physics, mut behavior, mut steering, speed) in query.iter_mut() {
         physics.acceleration += behavior.force;
 
         let acc_clone = physics.acceleration.clone();
@@ -178,6 +318,7 @@ fn deer_move(
         transform.translation += physics.velocity;
         physics.acceleration *= 0.0;
         behavior.force *= 0.0;
+        steering.flush();
 
         let angle = physics.velocity.y.atan2(physics.velocity.x) - PI / 2.0;
 
@@ -191,6 +332,7 @@ fn deer_alignment(
             &Transform,
             &Physics,
             &mut Behavior,
+            &mut SteeringDebug,
             &GroupID,
             &MovementSpeed,
         ),
@@ -201,11 +343,11 @@ fn deer_alignment(
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (transform, physics, mut behavior, id, speed) in query_mut.iter_mut() {
+    for (transform, physics, mut behavior, mut steering, id, speed) in query_mut.iter_mut() {
         let perception_radius: f32 = behavior_data.alignment.perception_radius;
         let mut steer = Vec3::default();
         let mut total = 0.0;
@@ -224,7 +366,7 @@ fn deer_alignment(
             steer = set_mag(steer, speed.value);
             steer -= physics.velocity;
             steer = limit(steer, behavior_data.alignment.max_force);
-            behavior.force += steer;
+            behavior.force += steering.record(SteeringKind::Alignment, steer);
         }
     }
 }
@@ -235,6 +377,7 @@ fn deer_cohesion(
             &Transform,
             &Physics,
             &mut Behavior,
+            &mut SteeringDebug,
             &GroupID,
             &MovementSpeed,
         ),
@@ -245,11 +388,11 @@ fn deer_cohesion(
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (transform, physics, mut behavior, id, speed) in query_mut.iter_mut() {
+    for (transform, physics, mut behavior, mut steering, id, speed) in query_mut.iter_mut() {
         let perception_radius: f32 = behavior_data.cohesion.perception_radius;
         let mut steer = Vec3::default();
         let mut total = 0.0;
@@ -269,7 +412,7 @@ fn deer_cohesion(
             steer = set_mag(steer, speed.value);
             steer -= physics.velocity;
             steer = limit(steer, behavior_data.cohesion.max_force);
-            behavior.force += steer;
+            behavior.force += steering.record(SteeringKind::Cohesion, steer);
         }
     }
 }
@@ -280,8 +423,10 @@ fn deer_separation(
             &Transform,
             &Physics,
             &mut Behavior,
+            &mut SteeringDebug,
             &GroupID,
             &MovementSpeed,
+            &Alarm,
         ),
         With<Deer>,
     >,
@@ -289,13 +434,21 @@ fn deer_separation(
     behavior_data: Res<DeerSteeringData>,
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
+    clock: Res<SimulationClock>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (transform, physics, mut behavior, id, speed) in query_mut.iter_mut() {
+    let now = clock.elapsed;
+
+    for (transform, physics, mut behavior, mut steering, id, speed, alarm) in query_mut.iter_mut() {
         let perception_radius: f32 = behavior_data.separation.perception_radius;
+        let max_force = if alarm.is_panicking(now) {
+            behavior_data.separation.max_force * behavior_data.alarm.separation_multiplier
+        } else {
+            behavior_data.separation.max_force
+        };
         let mut steer = Vec3::default();
         let mut total = 0.0;
 
@@ -315,25 +468,246 @@ fn deer_separation(
             steer /= total;
             steer = set_mag(steer, speed.value);
             steer -= physics.velocity;
-            steer = limit(steer, behavior_data.separation.max_force);
-            behavior.force += steer;
+            steer = limit(steer, max_force);
+            behavior.force += steering.record(SteeringKind::Separation, steer);
+        }
+    }
+}
+
+fn deer_regroup(
+    mut deer_query: Query<(Entity, &Transform, &mut GroupID), With<Deer>>,
+    mut deer_groups: ResMut<DeerGroups>,
+    settings: Res<DeerData>,
+    behavior_data: Res<DeerSteeringData>,
+    mut rng: ResMut<SimRng>,
+) {
+    if deer_groups.spawned < settings.group_number {
+        return;
+    }
+
+    let herd = &behavior_data.herd;
+
+    let members: Vec<(Entity, Vec3, u32)> = deer_query
+        .iter_mut()
+        .map(|(deer, transform, id)| (deer, transform.translation, id.value))
+        .collect();
+    let mut ids: Vec<u32> = members.iter().map(|member| member.2).collect();
+
+    let mut parents: Vec<usize> = (0..members.len()).collect();
+    for i in 0..members.len() {
+        for j in (i + 1)..members.len() {
+            if members[i].2 == members[j].2 && dist(members[i].1, members[j].1) < herd.split_radius
+            {
+                let root_i = find_root(&mut parents, i);
+                let root_j = find_root(&mut parents, j);
+                parents[root_j] = root_i;
+            }
+        }
+    }
+
+    let mut kept_roots: Vec<(u32, usize)> = Vec::new();
+    for group in deer_groups.groups.iter() {
+        if let Some(index) = members
+            .iter()
+            .position(|member| Some(member.0) == group.leader && member.2 == group.id)
+        {
+            kept_roots.push((group.id, find_root(&mut parents, index)));
+        }
+    }
+
+    let mut split_ids: Vec<(usize, u32)> = Vec::new();
+    for i in 0..members.len() {
+        let root = find_root(&mut parents, i);
+
+        match kept_roots.iter().find(|(id, _)| *id == members[i].2) {
+            Some((_, kept_root)) if *kept_root == root => {}
+            Some(_) => {
+                ids[i] = match split_ids.iter().find(|(split_root, _)| *split_root == root) {
+                    Some((_, id)) => *id,
+                    None => {
+                        let id = rng.gen();
+                        split_ids.push((root, id));
+                        id
+                    }
+                };
+            }
+            None => kept_roots.push((members[i].2, root)),
+        }
+    }
+
+    let mut sizes: HashMap<u32, usize> = HashMap::new();
+    for id in ids.iter() {
+        *sizes.entry(*id).or_insert(0) += 1;
+    }
+
+    for i in 0..members.len() {
+        for j in (i + 1)..members.len() {
+            if ids[i] == ids[j] || dist(members[i].1, members[j].1) >= herd.merge_radius {
+                continue;
+            }
+
+            let (from, to) = if sizes[&ids[i]] < sizes[&ids[j]] {
+                (ids[i], ids[j])
+            } else {
+                (ids[j], ids[i])
+            };
+
+            let moved = sizes.remove(&from).unwrap_or(0);
+            *sizes.entry(to).or_insert(0) += moved;
+
+            for id in ids.iter_mut() {
+                if *id == from {
+                    *id = to;
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<DeerGroup> = Vec::new();
+    for id in ids.iter() {
+        match groups.iter_mut().find(|group| group.id == *id) {
+            Some(group) => group.count += 1,
+            None => groups.push(DeerGroup {
+                id: *id,
+                count: 1,
+                leader: None,
+            }),
+        }
+    }
+
+    for group in groups.iter_mut() {
+        let previous_leader = deer_groups
+            .groups
+            .iter()
+            .find(|previous| previous.id == group.id)
+            .and_then(|previous| previous.leader);
+
+        let still_member = |leader: Entity| {
+            members
+                .iter()
+                .zip(ids.iter())
+                .any(|(member, id)| member.0 == leader && *id == group.id)
+        };
+
+        group.leader = match previous_leader {
+            Some(leader) if still_member(leader) => Some(leader),
+            _ => {
+                let mut centroid = Vec3::ZERO;
+                for (member, id) in members.iter().zip(ids.iter()) {
+                    if *id == group.id {
+                        centroid += member.1;
+                    }
+                }
+                centroid /= group.count as f32;
+
+                members
+                    .iter()
+                    .zip(ids.iter())
+                    .filter(|(_, id)| **id == group.id)
+                    .min_by(|(a, _), (b, _)| dist(a.1, centroid).total_cmp(&dist(b.1, centroid)))
+                    .map(|(member, _)| member.0)
+            }
+        };
+    }
+
+    deer_groups.groups = groups;
+
+    for (deer, _, mut id) in deer_query.iter_mut() {
+        if let Some(index) = members.iter().position(|member| member.0 == deer) {
+            if id.value != ids[index] {
+                id.value = ids[index];
+            }
         }
     }
 }
 
+fn find_root(parents: &mut Vec<usize>, index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+
+    parents[index] = root;
+    root
+}
+
+fn deer_follow_leader(
+    mut deer_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &GroupID,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
+    leader_query: Query<&Transform, With<Deer>>,
+    settings: Res<DeerData>,
+    deer_groups: Res<DeerGroups>,
+    behavior_data: Res<DeerSteeringData>,
+) {
+    if deer_groups.spawned < settings.group_number {
+        return;
+    }
+
+    for (deer, transform, physics, speed, id, mut behavior, mut steering) in deer_query.iter_mut() {
+        let leader = match deer_groups
+            .groups
+            .iter()
+            .find(|group| group.id == id.value)
+            .and_then(|group| group.leader)
+        {
+            Some(leader) if leader != deer => leader,
+            _ => continue,
+        };
+
+        let leader_transform = match leader_query.get(leader) {
+            Ok(transform) => transform,
+            Err(_) => continue,
+        };
+
+        if dist(transform.translation, leader_transform.translation)
+            < behavior_data.herd.follow_distance
+        {
+            continue;
+        }
+
+        let force = seek(
+            transform.translation,
+            physics.velocity,
+            leader_transform.translation,
+            speed.value * TIME_STEP,
+        );
+        behavior.force +=
+            steering.record(SteeringKind::Seek, force * behavior_data.herd.follow_weight);
+    }
+}
+
 fn deer_wander(
-    mut query: Query<(&Transform, &mut Physics, &mut Behavior, &GroupID), With<Deer>>,
+    mut query: Query<
+        (
+            &Transform,
+            &mut Physics,
+            &mut Behavior,
+            &mut SteeringDebug,
+            &GroupID,
+        ),
+        With<Deer>,
+    >,
 
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
     behavior_data: Res<DeerSteeringData>,
+    mut rng: ResMut<SimRng>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
     for group in deer_groups.groups.iter() {
-        let mut rng = rand::thread_rng();
         let displace_range: f32 = behavior_data.wander.displace_range;
         let mut displacements = vec![0.0; group.count as usize];
 
@@ -343,7 +717,7 @@ fn deer_wander(
 
         let mut count: usize = 0;
 
-        for (transform, mut physics, mut behavior, id) in query.iter_mut() {
+        for (transform, mut physics, mut behavior, mut steering, id) in query.iter_mut() {
             if group.id != id.value {
                 continue;
             }
@@ -356,7 +730,16 @@ fn deer_wander(
                 physics.wander_theta,
                 behavior_data.wander.max_force,
             );
-            behavior.force += force * behavior_data.wander.weight;
+
+            steering.record_wander(
+                transform.translation,
+                physics.velocity,
+                behavior_data.wander.radius,
+                behavior_data.wander.distance,
+                physics.wander_theta,
+            );
+            behavior.force +=
+                steering.record(SteeringKind::Wander, force * behavior_data.wander.weight);
             physics.wander_theta += displacements[count];
             count += 1;
         }
@@ -364,84 +747,197 @@ fn deer_wander(
 }
 
 fn deer_evade_walls(
-    mut deer_query: Query<(&Transform, &Physics, &MovementSpeed, &mut Behavior), With<Deer>>,
+    mut deer_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
     behavior_data: Res<DeerSteeringData>,
     walls: Res<Walls>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (transform, physics, speed, mut behavior) in deer_query.iter_mut() {
-        for wall in walls.value.iter() {
-            if let Ok(int) = line_line_intersection(
-                wall.point_a,
-                wall.point_b,
-                transform.translation,
-                transform.translation + physics.velocity,
-            ) {
-                if dist(transform.translation, int) > 40.0 {
-                    continue;
-                }
+    for (transform, physics, speed, mut behavior, mut steering) in deer_query.iter_mut() {
+        steering.record_wall_ray(
+            transform.translation,
+            physics.velocity,
+            behavior_data.evade_walls.look_ahead,
+        );
 
-                let force = flee(
-                    transform.translation,
-                    physics.velocity,
-                    int,
-                    speed.value * TIME_STEP,
-                );
-                behavior.force += force * behavior_data.evade_walls.weight;
+        for hit in walls.ray_hits(
+            transform.translation.truncate(),
+            physics.velocity.truncate(),
+        ) {
+            if hit.distance > behavior_data.evade_walls.look_ahead {
+                continue;
             }
+
+            let force = flee(
+                transform.translation,
+                physics.velocity,
+                hit.point.extend(0.0),
+                speed.value * TIME_STEP,
+            );
+            behavior.force += steering.record(
+                SteeringKind::Walls,
+                force * behavior_data.evade_walls.weight,
+            );
         }
     }
 }
 
 fn deer_flee(
-    mut deer_query: Query<(&Transform, &Physics, &MovementSpeed, &mut Behavior), With<Deer>>,
+    mut deer_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Perception,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
     threat_query: Query<&Transform, (With<Threat>, Without<Hare>)>,
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
     behavior_data: Res<DeerSteeringData>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (deer_transform, physics, speed, mut behavior) in deer_query.iter_mut() {
-        for threat_transform in threat_query.iter() {
-            if deer_transform == threat_transform {
-                continue;
-            }
+    for (deer_transform, physics, speed, perception, mut behavior, mut steering) in
+        deer_query.iter_mut()
+    {
+        for threat in perception.perceived.iter() {
+            let threat_transform = match threat_query.get(*threat) {
+                Ok(transform) => transform,
+                Err(_) => continue,
+            };
 
             let ds = dist(deer_transform.translation, threat_transform.translation);
-            if ds < 100.0 {
+            if ds < behavior_data.flee.radius {
                 let force = flee(
                     deer_transform.translation,
                     physics.velocity,
                     threat_transform.translation,
                     speed.value * TIME_STEP,
                 );
-                behavior.force += force * behavior_data.flee.weight;
+                behavior.force +=
+                    steering.record(SteeringKind::Flee, force * behavior_data.flee.weight);
+            }
+        }
+
+        for shot_position in perception.gunshots.iter() {
+            let force = flee(
+                deer_transform.translation,
+                physics.velocity,
+                *shot_position,
+                speed.value * TIME_STEP,
+            );
+            behavior.force +=
+                steering.record(SteeringKind::Flee, force * behavior_data.flee.weight);
+        }
+    }
+}
+
+fn deer_graze(
+    mut deer_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Perception,
+            &Alarm,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
+    food_query: Query<&FoodPatch>,
+    settings: Res<DeerData>,
+    deer_groups: Res<DeerGroups>,
+    behavior_data: Res<DeerSteeringData>,
+    clock: Res<SimulationClock>,
+) {
+    if deer_groups.spawned < settings.group_number {
+        return;
+    }
+
+    for (transform, physics, speed, perception, alarm, mut behavior, mut steering) in
+        deer_query.iter_mut()
+    {
+        if alarm.is_panicking(clock.elapsed) || !perception.gunshots.is_empty() {
+            continue;
+        }
+
+        let nearest = food_query
+            .iter()
+            .filter(|food_patch| {
+                dist(transform.translation, food_patch.position) < perception.range
+            })
+            .min_by(|a, b| {
+                dist(transform.translation, a.position)
+                    .total_cmp(&dist(transform.translation, b.position))
+            });
+
+        if let Some(food_patch) = nearest {
+            if dist(transform.translation, food_patch.position) < food_patch.radius {
+                continue;
             }
+
+            let force = seek(
+                transform.translation,
+                physics.velocity,
+                food_patch.position,
+                speed.value * TIME_STEP,
+            );
+            behavior.force +=
+                steering.record(SteeringKind::Seek, force * behavior_data.graze.weight);
         }
     }
 }
 
 fn deer_evade(
-    mut deer_query: Query<(&Transform, &Physics, &MovementSpeed, &mut Behavior), With<Deer>>,
+    mut deer_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Perception,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
     wolf_query: Query<(&Transform, &Physics), With<Wolf>>,
     settings: Res<DeerData>,
     deer_groups: Res<DeerGroups>,
     behavior_data: Res<DeerSteeringData>,
 ) {
-    if deer_groups.groups.len() < settings.group_number.try_into().unwrap() {
+    if deer_groups.spawned < settings.group_number {
         return;
     }
 
-    for (deer_transform, physics, speed, mut behavior) in deer_query.iter_mut() {
-        for (wolf_transform, prey_physics) in wolf_query.iter() {
+    for (deer_transform, physics, speed, perception, mut behavior, mut steering) in
+        deer_query.iter_mut()
+    {
+        for wolf in perception.perceived.iter() {
+            let (wolf_transform, prey_physics) = match wolf_query.get(*wolf) {
+                Ok(wolf) => wolf,
+                Err(_) => continue,
+            };
+
             let ds = dist(deer_transform.translation, wolf_transform.translation);
 
             let force = evade(
@@ -452,26 +948,115 @@ fn deer_evade(
                 speed.value * TIME_STEP,
             );
 
-            behavior.force += if ds > 180.0 {
-                Vec3::ZERO
-            } else {
-                force * behavior_data.evade.weight
-            };
+            if ds <= behavior_data.evade.radius {
+                behavior.force +=
+                    steering.record(SteeringKind::Flee, force * behavior_data.evade.weight);
+            }
+        }
+    }
+}
+
+fn deer_alarm(
+    mut deer_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &Perception,
+            &GroupID,
+            &mut Alarm,
+            &mut MovementSpeed,
+            &mut Behavior,
+            &mut SteeringDebug,
+        ),
+        With<Deer>,
+    >,
+    threat_query: Query<&Transform, (With<Threat>, Without<Hare>)>,
+    settings: Res<DeerData>,
+    deer_groups: Res<DeerGroups>,
+    behavior_data: Res<DeerSteeringData>,
+    clock: Res<SimulationClock>,
+) {
+    if deer_groups.spawned < settings.group_number {
+        return;
+    }
+
+    let now = clock.elapsed;
+    let alarm_data = &behavior_data.alarm;
+    let mut signals: Vec<(u32, Vec3, Vec3)> = Vec::new();
+
+    for (transform, physics, perception, id, mut alarm, mut speed, mut behavior, mut steering) in
+        deer_query.iter_mut()
+    {
+        let threat = perception
+            .perceived
+            .iter()
+            .filter_map(|threat| threat_query.get(*threat).ok())
+            .map(|threat_transform| threat_transform.translation)
+            .find(|position| dist(transform.translation, *position) < behavior_data.evade.radius)
+            .or_else(|| perception.gunshots.first().copied());
+
+        let was_panicking = alarm.is_panicking(now);
+
+        if let Some(origin) = threat {
+            alarm.origin = origin;
+            alarm.pending_at = None;
+            alarm.panic_until = now + alarm_data.decay;
+            signals.push((id.value, transform.translation, origin));
+        } else if alarm
+            .pending_at
+            .map_or(false, |pending_at| now >= pending_at)
+        {
+            alarm.pending_at = None;
+            alarm.panic_until = now + alarm_data.decay;
+            if !was_panicking {
+                signals.push((id.value, transform.translation, alarm.origin));
+            }
+        }
+
+        if alarm.is_panicking(now) {
+            speed.value = settings.movement_speed + alarm_data.speed_boost;
+
+            let force = flee(
+                transform.translation,
+                physics.velocity,
+                alarm.origin,
+                speed.value * TIME_STEP,
+            );
+            behavior.force += steering.record(SteeringKind::Flee, force * alarm_data.flee_weight);
+        } else {
+            speed.value = settings.movement_speed;
+        }
+    }
+
+    for (transform, _, _, id, mut alarm, _, _, _) in deer_query.iter_mut() {
+        if alarm.is_panicking(now) || alarm.pending_at.is_some() {
+            continue;
+        }
+
+        for (group, position, origin) in signals.iter() {
+            if *group == id.value && dist(transform.translation, *position) < alarm_data.radius {
+                alarm.pending_at = Some(now + alarm_data.delay);
+                alarm.origin = *origin;
+                break;
+            }
         }
     }
 }
 
 fn deer_die(
     mut commands: Commands,
-    deer_query: Query<(Entity, &Transform), With<Deer>>,
+    mut deer_query: Query<(Entity, &Transform, &mut Health), With<Deer>>,
     mut wolf_query: Query<(&Transform, &mut WolfBehavior), With<Wolf>>,
     bullet_query: Query<(Entity, &Transform), With<Bullet>>,
     deer_data: Res<DeerData>,
     wolf_data: Res<WolfData>,
     bullet_data: Res<BulletData>,
-    time: Res<Time>
+    clock: Res<SimulationClock>,
+    mut deaths: EventWriter<DeathEvent>,
 ) {
-    for (deer, deer_transform) in deer_query.iter() {
+    for (deer, deer_transform, mut health) in deer_query.iter_mut() {
+        let mut eaten = false;
+
         for (wolf_transform, mut behavior) in wolf_query.iter_mut() {
             if collide(
                 deer_transform.translation,
@@ -482,11 +1067,20 @@ fn deer_die(
             .is_some()
             {
                 commands.entity(deer).despawn();
-                behavior.hunger_time = time.seconds_since_startup() as f32;
+                behavior.feed(clock.elapsed);
+                deaths.send(DeathEvent {
+                    species: Species::Deer,
+                    cause: DeathCause::Eaten,
+                });
+                eaten = true;
                 break;
             }
         }
 
+        if eaten {
+            continue;
+        }
+
         for (bullet, bullet_transform) in bullet_query.iter() {
             if collide(
                 deer_transform.translation,
@@ -496,9 +1090,125 @@ fn deer_die(
             )
             .is_some()
             {
-                commands.entity(deer).despawn();
                 commands.entity(bullet).despawn();
+                health.value -= bullet_data.damage;
+
+                if health.value <= 0.0 {
+                    commands.entity(deer).despawn();
+                    deaths.send(DeathEvent {
+                        species: Species::Deer,
+                        cause: DeathCause::Shot,
+                    });
+                    break;
+                }
             }
         }
     }
 }
+
+pub(crate) fn save_snapshot(world: &mut World) -> Value {
+    let deer: Vec<Value> = world
+        .query_filtered::<(
+            Entity,
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Behavior,
+            &Alarm,
+            &GroupID,
+            &Health,
+            &Perception,
+        ), With<Deer>>()
+        .iter(world)
+        .map(
+            |(deer, transform, physics, speed, behavior, alarm, group, health, perception)| {
+                json!({
+                    "id": deer.id(),
+                    "transform": transform_value(transform),
+                    "physics": physics_value(physics),
+                    "movement_speed": speed.value,
+                    "force": vec3_value(behavior.force),
+                    "alarm": {
+                        "panic_until": alarm.panic_until,
+                        "pending_at": alarm.pending_at,
+                        "origin": vec3_value(alarm.origin),
+                    },
+                    "group": group.value,
+                    "health": health_value(health),
+                    "perception": perception_value(perception),
+                })
+            },
+        )
+        .collect();
+
+    let deer_groups = world.get_resource::<DeerGroups>().unwrap();
+    let groups: Vec<Value> = deer_groups
+        .groups
+        .iter()
+        .map(|group| {
+            json!({
+                "group": group.id,
+                "count": group.count,
+                "leader": entity_value(group.leader),
+            })
+        })
+        .collect();
+
+    json!({
+        "entities": deer,
+        "groups": groups,
+        "spawned": deer_groups.spawned,
+    })
+}
+
+pub(crate) fn load_snapshot(world: &mut World, snapshot: &Value, entities: &EntityMap) {
+    let material = world
+        .get_resource::<Materials>()
+        .unwrap()
+        .deer_material
+        .clone();
+
+    world.insert_resource(DeerGroups {
+        groups: snapshot["groups"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|group| DeerGroup {
+                id: get_u32(&group["group"], 0),
+                count: get_u32(&group["count"], 0),
+                leader: entities.get(&group["leader"]),
+            })
+            .collect(),
+        spawned: get_u32(&snapshot["spawned"], 0),
+    });
+
+    for (deer, record) in entities.records(&snapshot["entities"]) {
+        let alarm = &record["alarm"];
+
+        world
+            .entity_mut(deer)
+            .insert_bundle(SpriteBundle {
+                material: material.clone(),
+                transform: get_transform(&record["transform"]),
+                ..Default::default()
+            })
+            .insert(Deer)
+            .insert(Prey)
+            .insert(MovementSpeed::new(get_f32(&record["movement_speed"], 0.0)))
+            .insert(get_physics(&record["physics"]))
+            .insert(SteeringDebug::default())
+            .insert(Behavior {
+                force: get_vec3(&record["force"]),
+            })
+            .insert(Alarm {
+                panic_until: get_f32(&alarm["panic_until"], 0.0),
+                pending_at: alarm["pending_at"].as_f64().map(|time| time as f32),
+                origin: get_vec3(&alarm["origin"]),
+            })
+            .insert(GroupID {
+                value: get_u32(&record["group"], 0),
+            })
+            .insert(get_health(&record["health"]))
+            .insert(get_perception(&record["perception"], entities));
+    }
+}
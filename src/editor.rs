@@ -0,0 +1,293 @@
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    components::MousePosition,
+    geometry::point_segment_distance,
+    map::{FoodPatch, MapData, MapLayout, Obstacle, SpawnRegion},
+    stats::Species,
+    utils::dist,
+    WallData, FONT_PATH,
+};
+
+const ERASE_RADIUS: f32 = 20.0;
+const MIN_DRAG_DISTANCE: f32 = 5.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum EditorTool {
+    Wall,
+    Obstacle,
+    Burrow,
+    Den,
+    FoodPatch,
+    SpawnRegion,
+    Erase,
+}
+
+const TOOLS: [EditorTool; 7] = [
+    EditorTool::Wall,
+    EditorTool::Obstacle,
+    EditorTool::Burrow,
+    EditorTool::Den,
+    EditorTool::FoodPatch,
+    EditorTool::SpawnRegion,
+    EditorTool::Erase,
+];
+
+const REGION_SPECIES: [Species; 3] = [Species::Hare, Species::Wolf, Species::Deer];
+
+enum Feature {
+    Wall(usize),
+    Obstacle(usize),
+    Burrow(usize),
+    Den(usize),
+    FoodPatch(usize),
+    SpawnRegion(usize),
+}
+
+#[derive(Default)]
+struct Editor {
+    open: bool,
+    tool: usize,
+    species: usize,
+    drag_start: Option<Vec3>,
+    status: String,
+}
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Editor>()
+            .add_startup_system(editor_spawn.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                editor_input
+                    .system()
+                    .label("editor")
+                    .after(InputSystem)
+                    .after("cursor")
+                    .before("actions"),
+            )
+            .add_system(editor_update.system().label("editor_update"));
+    }
+}
+
+struct EditorText;
+
+fn editor_spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(EditorText);
+}
+
+fn editor_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mouse_position: Res<MousePosition>,
+    mut editor: ResMut<Editor>,
+    mut layout: ResMut<MapLayout>,
+    map_data: Res<MapData>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        editor.open = !editor.open;
+        editor.drag_start = None;
+    }
+
+    if !editor.open {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        editor.tool = (editor.tool + 1) % TOOLS.len();
+        editor.drag_start = None;
+    }
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        editor.species = (editor.species + 1) % REGION_SPECIES.len();
+    }
+
+    let control =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+
+    if control && keyboard_input.just_pressed(KeyCode::S) {
+        editor.status = match layout.save(&map_data.path) {
+            Ok(()) => format!("Saved to {}", map_data.path),
+            Err(error) => format!("Save failed: {}", error),
+        };
+    }
+    if control && keyboard_input.just_pressed(KeyCode::L) {
+        editor.status = match MapLayout::load(&map_data.path) {
+            Ok(loaded) => {
+                *layout = loaded;
+                format!("Loaded {}", map_data.path)
+            }
+            Err(error) => format!("Load failed: {}: {}", map_data.path, error),
+        };
+    }
+
+    let position = mouse_position.value;
+    let tool = TOOLS[editor.tool];
+
+    if mouse.just_pressed(MouseButton::Left) {
+        match tool {
+            EditorTool::Wall | EditorTool::SpawnRegion => editor.drag_start = Some(position),
+            EditorTool::Obstacle => layout.obstacles.push(Obstacle {
+                position,
+                radius: map_data.obstacle_radius,
+            }),
+            EditorTool::Burrow => layout.burrows.push(position),
+            EditorTool::Den => layout.dens.push(position),
+            EditorTool::FoodPatch => layout.food_patches.push(FoodPatch {
+                position,
+                radius: map_data.food_radius,
+            }),
+            EditorTool::Erase => {
+                if let Some(feature) = nearest_feature(&layout, position) {
+                    remove_feature(&mut layout, feature);
+                }
+            }
+        }
+    }
+
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(start) = editor.drag_start.take() {
+            if dist(start, position) > MIN_DRAG_DISTANCE {
+                match tool {
+                    EditorTool::Wall => layout.walls.push(WallData {
+                        point_a: start,
+                        point_b: position,
+                    }),
+                    EditorTool::SpawnRegion => layout.spawn_regions.push(SpawnRegion::new(
+                        REGION_SPECIES[editor.species],
+                        start,
+                        position,
+                    )),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    mouse.reset(MouseButton::Left);
+}
+
+fn nearest_feature(layout: &MapLayout, position: Vec3) -> Option<Feature> {
+    let mut features = Vec::new();
+
+    for (i, wall) in layout.walls.iter().enumerate() {
+        let ds = point_segment_distance(
+            position.truncate(),
+            wall.point_a.truncate(),
+            wall.point_b.truncate(),
+        );
+        features.push((Feature::Wall(i), ds));
+    }
+    for (i, obstacle) in layout.obstacles.iter().enumerate() {
+        let ds = (dist(position, obstacle.position) - obstacle.radius).max(0.0);
+        features.push((Feature::Obstacle(i), ds));
+    }
+    for (i, burrow) in layout.burrows.iter().enumerate() {
+        features.push((Feature::Burrow(i), dist(position, *burrow)));
+    }
+    for (i, den) in layout.dens.iter().enumerate() {
+        features.push((Feature::Den(i), dist(position, *den)));
+    }
+    for (i, food_patch) in layout.food_patches.iter().enumerate() {
+        let ds = (dist(position, food_patch.position) - food_patch.radius).max(0.0);
+        features.push((Feature::FoodPatch(i), ds));
+    }
+
+    let nearest = features
+        .into_iter()
+        .filter(|(_, ds)| *ds < ERASE_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(feature, _)| feature);
+
+    nearest.or_else(|| {
+        layout
+            .spawn_regions
+            .iter()
+            .rposition(|region| region.contains(position))
+            .map(Feature::SpawnRegion)
+    })
+}
+
+fn remove_feature(layout: &mut MapLayout, feature: Feature) {
+    match feature {
+        Feature::Wall(i) => {
+            layout.walls.remove(i);
+        }
+        Feature::Obstacle(i) => {
+            layout.obstacles.remove(i);
+        }
+        Feature::Burrow(i) => {
+            layout.burrows.remove(i);
+        }
+        Feature::Den(i) => {
+            layout.dens.remove(i);
+        }
+        Feature::FoodPatch(i) => {
+            layout.food_patches.remove(i);
+        }
+        Feature::SpawnRegion(i) => {
+            layout.spawn_regions.remove(i);
+        }
+    }
+}
+
+fn editor_update(
+    editor: Res<Editor>,
+    mut query: Query<(&mut Text, &mut Visible), With<EditorText>>,
+) {
+    for (mut text, mut visible) in query.iter_mut() {
+        visible.is_visible = editor.open;
+
+        if !editor.open {
+            continue;
+        }
+
+        let tool = TOOLS[editor.tool];
+        let mut lines = vec![format!("Editor - tool: {:?} (Tab)", tool)];
+
+        if tool == EditorTool::SpawnRegion {
+            lines.push(format!(
+                "Region species: {} (Q)",
+                REGION_SPECIES[editor.species].name()
+            ));
+        }
+        if let Some(start) = editor.drag_start {
+            lines.push(format!("Dragging from ({:.0}, {:.0})", start.x, start.y));
+        }
+
+        lines.push("Ctrl+S - save map, Ctrl+L - load map, F3 - close".to_string());
+
+        if !editor.status.is_empty() {
+            lines.push(editor.status.clone());
+        }
+
+        text.sections[0].value = lines.join("\n");
+    }
+}
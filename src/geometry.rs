@@ -0,0 +1,297 @@
+use bevy::math::Vec2;
+
+const EPSILON: f32 = 1e-6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    pub point: Vec2,
+    pub distance: f32,
+    pub normal: Vec2,
+}
+
+pub fn normalize_or_zero(vec: Vec2) -> Vec2 {
+    let length = vec.length();
+
+    if length > EPSILON {
+        vec / length
+    } else {
+        Vec2::ZERO
+    }
+}
+
+pub fn set_length(vec: Vec2, length: f32) -> Vec2 {
+    normalize_or_zero(vec) * length
+}
+
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+pub fn closest_point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_sq = ab.length_squared();
+
+    if length_sq <= EPSILON * EPSILON {
+        return a;
+    }
+
+    let t = ((point - a).dot(ab) / length_sq).max(0.0).min(1.0);
+
+    a + ab * t
+}
+
+pub fn point_segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    (point - closest_point_on_segment(point, a, b)).length()
+}
+
+pub fn ray_segment(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2) -> Option<Hit> {
+    let direction = normalize_or_zero(direction);
+
+    if direction == Vec2::ZERO {
+        return None;
+    }
+
+    cast(origin, direction, f32::INFINITY, a, b)
+}
+
+pub fn segment_segment(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Hit> {
+    let length = (a2 - a1).length();
+
+    if length <= EPSILON {
+        return if point_segment_distance(a1, b1, b2) <= EPSILON {
+            Some(Hit {
+                point: a1,
+                distance: 0.0,
+                normal: normalize_or_zero(perpendicular(b2 - b1)),
+            })
+        } else {
+            None
+        };
+    }
+
+    cast(a1, (a2 - a1) / length, length, b1, b2)
+}
+
+pub fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<Hit> {
+    let direction = normalize_or_zero(direction);
+
+    if direction == Vec2::ZERO {
+        return None;
+    }
+
+    let to_origin = origin - center;
+    let c = to_origin.length_squared() - radius * radius;
+
+    if c <= 0.0 {
+        return Some(Hit {
+            point: origin,
+            distance: 0.0,
+            normal: normalize_or_zero(to_origin),
+        });
+    }
+
+    let b = to_origin.dot(direction);
+    let discriminant = b * b - c;
+
+    if b > 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let distance = -b - discriminant.sqrt();
+    let point = origin + direction * distance;
+
+    Some(Hit {
+        point,
+        distance,
+        normal: normalize_or_zero(point - center),
+    })
+}
+
+fn cast(origin: Vec2, direction: Vec2, max_distance: f32, a: Vec2, b: Vec2) -> Option<Hit> {
+    let edge = b - a;
+    let to_a = a - origin;
+    let den = cross(direction, edge);
+
+    if den.abs() <= EPSILON * edge.length() {
+        if cross(to_a, direction).abs() > EPSILON * to_a.length().max(1.0) {
+            return None;
+        }
+
+        let along_a = to_a.dot(direction);
+        let along_b = (b - origin).dot(direction);
+        let start = along_a.min(along_b).max(0.0);
+        let end = along_a.max(along_b).min(max_distance);
+
+        if start > end {
+            return None;
+        }
+
+        return Some(Hit {
+            point: origin + direction * start,
+            distance: start,
+            normal: -direction,
+        });
+    }
+
+    let distance = cross(to_a, edge) / den;
+    let u = cross(to_a, direction) / den;
+
+    if distance < 0.0 || distance > max_distance || !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let mut normal = normalize_or_zero(perpendicular(edge));
+    if normal.dot(direction) > 0.0 {
+        normal = -normal;
+    }
+
+    Some(Hit {
+        point: origin + direction * distance,
+        distance,
+        normal,
+    })
+}
+
+fn perpendicular(vec: Vec2) -> Vec2 {
+    Vec2::new(-vec.y, vec.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{cases, random_vec2};
+    use rand::Rng;
+
+    const TOLERANCE: f32 = 1e-2;
+
+    #[test]
+    fn normalize_or_zero_handles_zero() {
+        assert_eq!(normalize_or_zero(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(set_length(Vec2::ZERO, 5.0), Vec2::ZERO);
+        assert_eq!(normalize_or_zero(Vec2::new(3.0, 4.0)), Vec2::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn ray_hits_wall_in_front() {
+        let hit = ray_segment(
+            Vec2::new(-20.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, -10.0),
+            Vec2::new(0.0, 10.0),
+        )
+        .unwrap();
+
+        assert_eq!(hit.point, Vec2::ZERO);
+        assert_eq!(hit.distance, 20.0);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn ray_misses_wall_behind() {
+        let hit = ray_segment(
+            Vec2::new(20.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, -10.0),
+            Vec2::new(0.0, 10.0),
+        );
+
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn parallel_segments_do_not_intersect() {
+        let hit = segment_segment(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 5.0),
+            Vec2::new(10.0, 5.0),
+        );
+
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn collinear_segments_hit_at_first_overlap() {
+        let hit = segment_segment(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(20.0, 0.0),
+        )
+        .unwrap();
+
+        assert_eq!(hit.point, Vec2::new(4.0, 0.0));
+        assert_eq!(hit.distance, 4.0);
+    }
+
+    #[test]
+    fn segment_segment_hit_lies_on_both_segments() {
+        cases(|rng| {
+            let a1 = random_vec2(rng, 100.0);
+            let a2 = random_vec2(rng, 100.0);
+            let b1 = random_vec2(rng, 100.0);
+            let b2 = random_vec2(rng, 100.0);
+
+            if let Some(hit) = segment_segment(a1, a2, b1, b2) {
+                assert!(point_segment_distance(hit.point, a1, a2) < TOLERANCE);
+                assert!(point_segment_distance(hit.point, b1, b2) < TOLERANCE);
+                assert!((hit.distance - (hit.point - a1).length()).abs() < TOLERANCE);
+                assert!((hit.normal.length() - 1.0).abs() < TOLERANCE);
+                assert!(hit.normal.dot(a2 - a1) <= TOLERANCE);
+            }
+        });
+    }
+
+    #[test]
+    fn segment_segment_is_symmetric_in_hitting() {
+        cases(|rng| {
+            let a1 = random_vec2(rng, 100.0);
+            let a2 = random_vec2(rng, 100.0);
+            let b1 = random_vec2(rng, 100.0);
+            let b2 = random_vec2(rng, 100.0);
+
+            assert_eq!(
+                segment_segment(a1, a2, b1, b2).is_some(),
+                segment_segment(b1, b2, a1, a2).is_some()
+            );
+        });
+    }
+
+    #[test]
+    fn ray_circle_hit_lies_on_circle() {
+        cases(|rng| {
+            let origin = random_vec2(rng, 100.0);
+            let center = random_vec2(rng, 100.0);
+            let radius = rng.gen_range(1.0..50.0);
+
+            if let Some(hit) = ray_circle(origin, center - origin, center, radius) {
+                if hit.distance > 0.0 {
+                    assert!(((hit.point - center).length() - radius).abs() < TOLERANCE);
+                    assert!((hit.distance - (hit.point - origin).length()).abs() < TOLERANCE);
+                }
+            } else {
+                panic!("a ray aimed at the center must hit the circle");
+            }
+        });
+    }
+
+    #[test]
+    fn ray_circle_misses_when_pointing_away() {
+        let hit = ray_circle(Vec2::new(100.0, 0.0), Vec2::new(1.0, 0.0), Vec2::ZERO, 10.0);
+
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn point_segment_distance_is_never_more_than_endpoint_distance() {
+        cases(|rng| {
+            let point = random_vec2(rng, 100.0);
+            let a = random_vec2(rng, 100.0);
+            let b = random_vec2(rng, 100.0);
+            let distance = point_segment_distance(point, a, b);
+
+            assert!(distance <= (point - a).length() + TOLERANCE);
+            assert!(distance <= (point - b).length() + TOLERANCE);
+        });
+    }
+}
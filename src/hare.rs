@@ -1,8 +1,20 @@
 use crate::{
-    components::{MovementSpeed, Prey, Threat},
+    components::{Health, Hidden, MovementSpeed, Prey, Threat},
+    map::{FoodPatch, MapLayout},
+    perception::{Perception, PerceptionData},
     player::{Bullet, BulletData},
-    steering::{flee, wander, EvadeWallsData, FleeData, Physics, WanderData},
-    utils::{dist, limit, line_line_intersection},
+    settings::{get_f32, get_u32},
+    simulation::{SimRng, SimulationClock, SIMULATION},
+    snapshot::{
+        get_health, get_perception, get_physics, get_transform, get_vec3, health_value,
+        perception_value, physics_value, transform_value, vec3_value, EntityMap,
+    },
+    stats::{BirthEvent, DeathCause, DeathEvent, Species},
+    steering::{
+        flee, seek, wander, EvadeWallsData, FleeData, Physics, SeekData, SteeringDebug,
+        SteeringKind, WanderData,
+    },
+    utils::{dist, limit},
     wolf::{Wolf, WolfBehavior, WolfData},
     FieldSize, Materials, Walls, TIME_STEP,
 };
@@ -13,10 +25,15 @@ use bevy::{
 };
 
 use rand::Rng;
+use serde_json::{json, Value};
 use std::f32::consts::PI;
 
 pub(crate) struct Hare;
 
+pub(crate) struct Burrow {
+    pub radius: f32,
+}
+
 struct ActiveHares {
     count: u32,
 }
@@ -33,12 +50,24 @@ pub(crate) struct HareData {
     pub width: f32,
     pub height: f32,
     pub max_number: u32,
+    pub health: f32,
+    pub spawn_margin: f32,
+    pub perception: PerceptionData,
+    pub burrow: BurrowData,
+}
+
+pub struct BurrowData {
+    pub number: u32,
+    pub radius: f32,
+    pub emerge_delay: f32,
 }
 
 pub struct HareSteeringData {
     pub wander: WanderData,
     pub flee: FleeData,
     pub evade_walls: EvadeWallsData,
+    pub seek_burrow: SeekData,
+    pub graze: SeekData,
 }
 
 struct HareBehavior {
@@ -50,17 +79,49 @@ pub struct HarePlugin;
 
 impl Plugin for HarePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(hare_spawn.system().label("hare_spawn"))
+        app.add_system_to_stage(SIMULATION, hare_spawn.system().label("hare_spawn"))
             .insert_resource(ActiveHares::default())
-            .add_system(
+            .add_startup_stage(
+                "game_setup_burrows",
+                SystemStage::single(burrow_spawn.system().label("burrow_spawn")),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 hare_flee
                     .system()
                     .label("hare_flee")
                     .before("hare_movement")
-                    .after("hare_spawn"),
+                    .after("hare_spawn")
+                    .after("perception"),
             )
-            .add_system(hare_move.system().label("hare_movement"))
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
+                hare_seek_burrow
+                    .system()
+                    .label("hare_seek_burrow")
+                    .after("hare_flee")
+                    .before("hare_movement"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                hare_graze
+                    .system()
+                    .label("hare_graze")
+                    .after("hare_flee")
+                    .after("perception")
+                    .before("hare_movement"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                hare_hide
+                    .system()
+                    .label("hare_hide")
+                    .after("hare_flee")
+                    .after("perception"),
+            )
+            .add_system_to_stage(SIMULATION, hare_move.system().label("hare_movement"))
+            .add_system_to_stage(
+                SIMULATION,
                 hare_wander
                     .system()
                     .label("hare_wander")
@@ -68,7 +129,8 @@ impl Plugin for HarePlugin {
                     .before("hare_movement")
                     .after("hare_spawn"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
                 hare_evade_walls
                     .system()
                     .label("hare_avoid_walls")
@@ -76,7 +138,7 @@ impl Plugin for HarePlugin {
                     .before("hare_movement")
                     .after("hare_spawn"),
             )
-            .add_system(hare_die.system().label("hare_die"));
+            .add_system_to_stage(SIMULATION, hare_die.system().label("hare_die"));
     }
 }
 
@@ -86,13 +148,13 @@ fn hare_spawn(
     mut active_hares: ResMut<ActiveHares>,
     filed_size: Res<FieldSize>,
     settings: Res<HareData>,
+    layout: Res<MapLayout>,
+    mut rng: ResMut<SimRng>,
+    mut births: EventWriter<BirthEvent>,
 ) {
     if active_hares.count < settings.max_number {
-        let mut rng = rand::thread_rng();
-        let w_span = filed_size.width / 2.0 - 30.0;
-        let h_span = filed_size.height / 2.0 - 30.0;
-        let x = rng.gen_range(-w_span..w_span) as f32;
-        let y = rng.gen_range(-h_span..h_span) as f32;
+        let Vec3 { x, y, .. } =
+            layout.spawn_point(Species::Hare, &filed_size, settings.spawn_margin, &mut *rng);
 
         commands
             .spawn_bundle(SpriteBundle {
@@ -113,24 +175,52 @@ fn hare_spawn(
                 acceleration: Vec3::default(),
                 wander_theta: PI / 2.0,
             })
+            .insert(SteeringDebug::default())
             .insert(HareBehavior {
                 force: Vec3::ZERO,
                 flee_time: 0.0,
-            });
+            })
+            .insert(Health::new(settings.health))
+            .insert(Perception::new(&settings.perception));
 
+        births.send(BirthEvent {
+            species: Species::Hare,
+        });
         active_hares.count += 1;
     }
 }
 
+fn burrow_spawn(
+    mut layout: ResMut<MapLayout>,
+    filed_size: Res<FieldSize>,
+    settings: Res<HareData>,
+    mut rng: ResMut<SimRng>,
+) {
+    if !layout.burrows.is_empty() {
+        return;
+    }
+
+    let w_span = filed_size.width / 2.0 - settings.spawn_margin;
+    let h_span = filed_size.height / 2.0 - settings.spawn_margin;
+
+    for _ in 0..settings.burrow.number {
+        let x = rng.gen_range(-w_span..w_span) as f32;
+        let y = rng.gen_range(-h_span..h_span) as f32;
+
+        layout.burrows.push(Vec3::new(x, y, 0.0));
+    }
+}
+
 fn hare_move(
     mut query: Query<
         (
             &mut Transform,
             &mut Physics,
             &mut HareBehavior,
+            &mut SteeringDebug,
             &mut MovementSpeed,
         ),
-        With<Hare>,
+        (With<Hare>, Without<Hidden>),
     >,
     active_hares: Res<ActiveHares>,
     settings: Res<HareData>,
@@ -139,7 +229,7 @@ fn hare_move(
         return;
     }
 
-    for (mut transform, mut physics, mut behavior, speed) in query.iter_mut() {
+    for (mut transform, mut physics, mut behavior, mut steering, speed) in query.iter_mut() {
         physics.acceleration += behavior.force;
 
         let acc_clone = physics.acceleration.clone();
@@ -148,6 +238,7 @@ fn hare_move(
         transform.translation += physics.velocity;
         physics.acceleration *= 0.0;
         behavior.force *= 0.0;
+        steering.flush();
 
         let angle = physics.velocity.y.atan2(physics.velocity.x) - PI / 2.0;
 
@@ -156,16 +247,24 @@ fn hare_move(
 }
 
 fn hare_wander(
-    mut query: Query<(&Transform, &mut Physics, &mut HareBehavior), With<Hare>>,
+    mut query: Query<
+        (
+            &Transform,
+            &mut Physics,
+            &mut HareBehavior,
+            &mut SteeringDebug,
+        ),
+        (With<Hare>, Without<Hidden>),
+    >,
     active_hares: Res<ActiveHares>,
     settings: Res<HareData>,
     behavior_data: Res<HareSteeringData>,
+    mut rng: ResMut<SimRng>,
 ) {
     if active_hares.count < settings.max_number {
         return;
     }
 
-    let mut rng = rand::thread_rng();
     let displace_range: f32 = behavior_data.wander.displace_range;
     let mut displacements = vec![0.0; settings.max_number as usize];
 
@@ -175,7 +274,7 @@ fn hare_wander(
 
     let mut count: usize = 0;
 
-    for (transform, mut physics, mut behavior) in query.iter_mut() {
+    for (transform, mut physics, mut behavior, mut steering) in query.iter_mut() {
         let force = wander(
             transform.translation,
             physics.velocity,
@@ -184,7 +283,15 @@ fn hare_wander(
             physics.wander_theta,
             behavior_data.wander.max_force,
         );
-        behavior.force += force * behavior_data.wander.weight;
+        steering.record_wander(
+            transform.translation,
+            physics.velocity,
+            behavior_data.wander.radius,
+            behavior_data.wander.distance,
+            physics.wander_theta,
+        );
+        behavior.force +=
+            steering.record(SteeringKind::Wander, force * behavior_data.wander.weight);
         physics.wander_theta += displacements[count];
         count += 1;
     }
@@ -192,35 +299,45 @@ fn hare_wander(
 
 fn hare_flee(
     mut hare_query: Query<
-        (&Transform, &Physics, &mut MovementSpeed, &mut HareBehavior),
-        With<Hare>,
+        (
+            &Transform,
+            &Physics,
+            &Perception,
+            &mut MovementSpeed,
+            &mut HareBehavior,
+            &mut SteeringDebug,
+        ),
+        (With<Hare>, Without<Hidden>),
     >,
     threat_query: Query<&Transform, With<Threat>>,
     active_hares: Res<ActiveHares>,
     settings: Res<HareData>,
     behavior_data: Res<HareSteeringData>,
-    time: Res<Time>,
+    clock: Res<SimulationClock>,
 ) {
     if active_hares.count < settings.max_number {
         return;
     }
 
-    for (hare_transform, physics, mut speed, mut behavior) in hare_query.iter_mut() {
-        for threat_transform in threat_query.iter() {
-            if hare_transform == threat_transform {
-                continue;
-            }
+    for (hare_transform, physics, perception, mut speed, mut behavior, mut steering) in
+        hare_query.iter_mut()
+    {
+        let now = clock.elapsed;
 
-            let now = time.seconds_since_startup();
+        if now >= (behavior.flee_time + behavior_data.flee.max_flee_time) {
+            behavior.flee_time = 0.0;
+            speed.value = settings.movement_speed;
+        }
 
-            if now >= (behavior.flee_time + behavior_data.flee.max_flee_time).into() {
-                behavior.flee_time = 0.0;
-                speed.value = settings.movement_speed;
-            }
+        for threat in perception.perceived.iter() {
+            let threat_transform = match threat_query.get(*threat) {
+                Ok(transform) => transform,
+                Err(_) => continue,
+            };
 
             let ds = dist(hare_transform.translation, threat_transform.translation);
-            if ds < 100.0 {
-                speed.value = settings.movement_speed + 50.0;
+            if ds < behavior_data.flee.radius {
+                speed.value = settings.movement_speed + behavior_data.flee.speed_boost;
                 let force = flee(
                     hare_transform.translation,
                     physics.velocity,
@@ -228,59 +345,256 @@ fn hare_flee(
                     speed.value * TIME_STEP,
                 );
 
-                behavior.flee_time = now as f32;
-                behavior.force += force * behavior_data.flee.weight;
+                behavior.flee_time = now;
+                behavior.force +=
+                    steering.record(SteeringKind::Flee, force * behavior_data.flee.weight);
             }
         }
+
+        for shot_position in perception.gunshots.iter() {
+            speed.value = settings.movement_speed + behavior_data.flee.speed_boost;
+            let force = flee(
+                hare_transform.translation,
+                physics.velocity,
+                *shot_position,
+                speed.value * TIME_STEP,
+            );
+
+            behavior.flee_time = now;
+            behavior.force +=
+                steering.record(SteeringKind::Flee, force * behavior_data.flee.weight);
+        }
     }
 }
 
-fn hare_evade_walls(
-    mut hare_query: Query<(&Transform, &Physics, &MovementSpeed, &mut HareBehavior), With<Hare>>,
+fn hare_seek_burrow(
+    mut hare_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &mut HareBehavior,
+            &mut SteeringDebug,
+        ),
+        (With<Hare>, Without<Hidden>),
+    >,
+    burrow_query: Query<&Transform, With<Burrow>>,
+    active_hares: Res<ActiveHares>,
+    settings: Res<HareData>,
+    behavior_data: Res<HareSteeringData>,
+) {
+    if active_hares.count < settings.max_number {
+        return;
+    }
+
+    for (transform, physics, speed, mut behavior, mut steering) in hare_query.iter_mut() {
+        if behavior.flee_time == 0.0 {
+            continue;
+        }
+
+        let nearest = burrow_query.iter().min_by(|a, b| {
+            dist(transform.translation, a.translation)
+                .total_cmp(&dist(transform.translation, b.translation))
+        });
+
+        if let Some(burrow_transform) = nearest {
+            let force = seek(
+                transform.translation,
+                physics.velocity,
+                burrow_transform.translation,
+                speed.value * TIME_STEP,
+            );
+            behavior.force +=
+                steering.record(SteeringKind::Seek, force * behavior_data.seek_burrow.weight);
+        }
+    }
+}
+
+fn hare_graze(
+    mut hare_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Perception,
+            &mut HareBehavior,
+            &mut SteeringDebug,
+        ),
+        (With<Hare>, Without<Hidden>),
+    >,
+    food_query: Query<&FoodPatch>,
     active_hares: Res<ActiveHares>,
     settings: Res<HareData>,
     behavior_data: Res<HareSteeringData>,
-    walls: Res<Walls>,
 ) {
     if active_hares.count < settings.max_number {
         return;
     }
 
-    for (transform, physics, speed, mut behavior) in hare_query.iter_mut() {
-        for wall in walls.value.iter() {
-            if let Ok(int) = line_line_intersection(
-                wall.point_a,
-                wall.point_b,
+    for (transform, physics, speed, perception, mut behavior, mut steering) in hare_query.iter_mut()
+    {
+        if behavior.flee_time != 0.0 {
+            continue;
+        }
+
+        let nearest = food_query
+            .iter()
+            .filter(|food_patch| {
+                dist(transform.translation, food_patch.position) < perception.range
+            })
+            .min_by(|a, b| {
+                dist(transform.translation, a.position)
+                    .total_cmp(&dist(transform.translation, b.position))
+            });
+
+        if let Some(food_patch) = nearest {
+            if dist(transform.translation, food_patch.position) < food_patch.radius {
+                continue;
+            }
+
+            let force = seek(
                 transform.translation,
-                transform.translation + physics.velocity,
-            ) {
-                if dist(transform.translation, int) > 40.0 {
+                physics.velocity,
+                food_patch.position,
+                speed.value * TIME_STEP,
+            );
+            behavior.force +=
+                steering.record(SteeringKind::Seek, force * behavior_data.graze.weight);
+        }
+    }
+}
+
+fn hare_hide(
+    mut commands: Commands,
+    mut hare_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Visible,
+            &mut HareBehavior,
+            &Perception,
+            Option<&mut Hidden>,
+        ),
+        With<Hare>,
+    >,
+    burrow_query: Query<(&Transform, &Burrow), Without<Hare>>,
+    threat_query: Query<&Transform, (With<Threat>, Without<Hare>)>,
+    active_hares: Res<ActiveHares>,
+    settings: Res<HareData>,
+    behavior_data: Res<HareSteeringData>,
+    clock: Res<SimulationClock>,
+) {
+    if active_hares.count < settings.max_number {
+        return;
+    }
+
+    let now = clock.elapsed;
+
+    for (hare, mut transform, mut visible, mut behavior, perception, hidden) in
+        hare_query.iter_mut()
+    {
+        match hidden {
+            Some(mut hidden) => {
+                let threatened = !perception.gunshots.is_empty()
+                    || perception
+                        .perceived
+                        .iter()
+                        .filter_map(|threat| threat_query.get(*threat).ok())
+                        .any(|threat_transform| {
+                            dist(transform.translation, threat_transform.translation)
+                                < behavior_data.flee.radius
+                        });
+
+                if threatened {
+                    hidden.last_threat = now;
+                } else if now - hidden.last_threat > settings.burrow.emerge_delay {
+                    commands.entity(hare).remove::<Hidden>();
+                    visible.is_visible = true;
+                    behavior.flee_time = 0.0;
+                }
+            }
+            None => {
+                if behavior.flee_time == 0.0 {
                     continue;
                 }
 
-                let force = flee(
-                    transform.translation,
-                    physics.velocity,
-                    int,
-                    speed.value * TIME_STEP,
-                );
-                behavior.force += force * behavior_data.evade_walls.weight;
+                for (burrow_transform, burrow) in burrow_query.iter() {
+                    if dist(transform.translation, burrow_transform.translation) < burrow.radius {
+                        transform.translation.x = burrow_transform.translation.x;
+                        transform.translation.y = burrow_transform.translation.y;
+                        visible.is_visible = false;
+                        commands.entity(hare).insert(Hidden { last_threat: now });
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn hare_evade_walls(
+    mut hare_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &mut HareBehavior,
+            &mut SteeringDebug,
+        ),
+        (With<Hare>, Without<Hidden>),
+    >,
+    active_hares: Res<ActiveHares>,
+    settings: Res<HareData>,
+    behavior_data: Res<HareSteeringData>,
+    walls: Res<Walls>,
+) {
+    if active_hares.count < settings.max_number {
+        return;
+    }
+
+    for (transform, physics, speed, mut behavior, mut steering) in hare_query.iter_mut() {
+        steering.record_wall_ray(
+            transform.translation,
+            physics.velocity,
+            behavior_data.evade_walls.look_ahead,
+        );
+
+        for hit in walls.ray_hits(
+            transform.translation.truncate(),
+            physics.velocity.truncate(),
+        ) {
+            if hit.distance > behavior_data.evade_walls.look_ahead {
+                continue;
             }
+
+            let force = flee(
+                transform.translation,
+                physics.velocity,
+                hit.point.extend(0.0),
+                speed.value * TIME_STEP,
+            );
+            behavior.force += steering.record(
+                SteeringKind::Walls,
+                force * behavior_data.evade_walls.weight,
+            );
         }
     }
 }
 
 fn hare_die(
     mut commands: Commands,
-    hare_query: Query<(Entity, &Transform), With<Hare>>,
+    mut hare_query: Query<(Entity, &Transform, &mut Health), (With<Hare>, Without<Hidden>)>,
     mut wolf_query: Query<(&Transform, &mut WolfBehavior), With<Wolf>>,
     bullet_query: Query<(Entity, &Transform), With<Bullet>>,
     hare_data: Res<HareData>,
     wolf_data: Res<WolfData>,
     bullet_data: Res<BulletData>,
-    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    mut deaths: EventWriter<DeathEvent>,
 ) {
-    for (hare, hare_transform) in hare_query.iter() {
+    for (hare, hare_transform, mut health) in hare_query.iter_mut() {
+        let mut eaten = false;
+
         for (wolf_transform, mut behavior) in wolf_query.iter_mut() {
             if collide(
                 hare_transform.translation,
@@ -291,11 +605,20 @@ fn hare_die(
             .is_some()
             {
                 commands.entity(hare).despawn();
-                behavior.hunger_time = time.seconds_since_startup() as f32;
+                behavior.feed(clock.elapsed);
+                deaths.send(DeathEvent {
+                    species: Species::Hare,
+                    cause: DeathCause::Eaten,
+                });
+                eaten = true;
                 break;
             }
         }
 
+        if eaten {
+            continue;
+        }
+
         for (bullet, bullet_transform) in bullet_query.iter() {
             if collide(
                 hare_transform.translation,
@@ -305,9 +628,98 @@ fn hare_die(
             )
             .is_some()
             {
-                commands.entity(hare).despawn();
                 commands.entity(bullet).despawn();
+                health.value -= bullet_data.damage;
+
+                if health.value <= 0.0 {
+                    commands.entity(hare).despawn();
+                    deaths.send(DeathEvent {
+                        species: Species::Hare,
+                        cause: DeathCause::Shot,
+                    });
+                    break;
+                }
             }
         }
     }
 }
+
+pub(crate) fn save_snapshot(world: &mut World) -> Value {
+    let hares: Vec<Value> = world
+        .query_filtered::<(
+            Entity,
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &HareBehavior,
+            &Health,
+            &Perception,
+            Option<&Hidden>,
+        ), With<Hare>>()
+        .iter(world)
+        .map(
+            |(hare, transform, physics, speed, behavior, health, perception, hidden)| {
+                json!({
+                    "id": hare.id(),
+                    "transform": transform_value(transform),
+                    "physics": physics_value(physics),
+                    "movement_speed": speed.value,
+                    "force": vec3_value(behavior.force),
+                    "flee_time": behavior.flee_time,
+                    "health": health_value(health),
+                    "perception": perception_value(perception),
+                    "hidden": hidden.map(|hidden| hidden.last_threat),
+                })
+            },
+        )
+        .collect();
+
+    json!({
+        "active": world.get_resource::<ActiveHares>().unwrap().count,
+        "entities": hares,
+    })
+}
+
+pub(crate) fn load_snapshot(world: &mut World, snapshot: &Value, entities: &EntityMap) {
+    let material = world
+        .get_resource::<Materials>()
+        .unwrap()
+        .hare_material
+        .clone();
+
+    world.get_resource_mut::<ActiveHares>().unwrap().count = get_u32(&snapshot["active"], 0);
+
+    for (hare, record) in entities.records(&snapshot["entities"]) {
+        let hidden = record["hidden"].as_f64();
+        let mut entity = world.entity_mut(hare);
+
+        entity
+            .insert_bundle(SpriteBundle {
+                material: material.clone(),
+                transform: get_transform(&record["transform"]),
+                visible: Visible {
+                    is_visible: hidden.is_none(),
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(Hare)
+            .insert(Threat)
+            .insert(Prey)
+            .insert(MovementSpeed::new(get_f32(&record["movement_speed"], 0.0)))
+            .insert(get_physics(&record["physics"]))
+            .insert(SteeringDebug::default())
+            .insert(HareBehavior {
+                force: get_vec3(&record["force"]),
+                flee_time: get_f32(&record["flee_time"], 0.0),
+            })
+            .insert(get_health(&record["health"]))
+            .insert(get_perception(&record["perception"], entities));
+
+        if let Some(last_threat) = hidden {
+            entity.insert(Hidden {
+                last_threat: last_threat as f32,
+            });
+        }
+    }
+}
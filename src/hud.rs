@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{
+    player::{Player, PlayerData, Stamina},
+    stats::{Score, SimulationStats},
+    FONT_PATH,
+};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(hud_spawn.system())
+            .add_system(hud_update.system().label("hud"));
+    }
+}
+
+struct Hud;
+
+fn hud_spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(Hud);
+}
+
+fn hud_update(
+    score: Res<Score>,
+    stats: Res<SimulationStats>,
+    player_data: Res<PlayerData>,
+    stamina_query: Query<&Stamina, With<Player>>,
+    mut query: Query<&mut Text, With<Hud>>,
+) {
+    let stamina_text = match stamina_query.single() {
+        Ok(stamina) if stamina.exhausted => "exhausted".to_string(),
+        Ok(stamina) => format!(
+            "{:.0}%",
+            stamina.value / player_data.stamina.max(f32::EPSILON) * 100.0
+        ),
+        Err(_) => "-".to_string(),
+    };
+
+    let hunger_text = match stats.nearest_wolf_hunger {
+        Some(hunger) => format!("{:.0}%", hunger.max(0.0) * 100.0),
+        None => "-".to_string(),
+    };
+
+    let minutes = (stats.elapsed / 60.0) as u32;
+    let seconds = (stats.elapsed % 60.0) as u32;
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Score: {}\nStamina: {}\nHares: {}  Wolves: {}  Deer: {}\nNearest wolf hunger: {}\nTime: {:02}:{:02}",
+            score.value,
+            stamina_text,
+            stats.hares,
+            stats.wolves,
+            stats.deer,
+            hunger_text,
+            minutes,
+            seconds
+        );
+    }
+}
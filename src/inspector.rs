@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Health, MousePosition, MovementSpeed},
+    deer::{Deer, GroupID},
+    hare::Hare,
+    player::Player,
+    simulation::SimulationClock,
+    steering::{Physics, SteeringDebug},
+    targeting::Target,
+    utils::dist,
+    wolf::{PackID, Wolf, WolfBehavior},
+    FONT_PATH,
+};
+
+const SELECT_RADIUS: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum InspectorField {
+    VelocityX,
+    VelocityY,
+    WanderTheta,
+    MovementSpeed,
+    Hunger,
+}
+
+const FIELDS: [InspectorField; 5] = [
+    InspectorField::VelocityX,
+    InspectorField::VelocityY,
+    InspectorField::WanderTheta,
+    InspectorField::MovementSpeed,
+    InspectorField::Hunger,
+];
+
+impl InspectorField {
+    fn step(&self) -> f32 {
+        match self {
+            InspectorField::VelocityX | InspectorField::VelocityY => 0.1,
+            InspectorField::WanderTheta => 0.1,
+            InspectorField::MovementSpeed => 10.0,
+            InspectorField::Hunger => 0.1,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Selection {
+    pub entity: Option<Entity>,
+    pub follow: bool,
+    field: usize,
+}
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Selection>()
+            .add_startup_system(inspector_spawn.system())
+            .add_system(inspector_select.system().label("inspector_select"))
+            .add_system(
+                inspector_edit
+                    .system()
+                    .label("inspector_edit")
+                    .after("inspector_select"),
+            )
+            .add_system(
+                inspector_update
+                    .system()
+                    .label("inspector")
+                    .after("inspector_edit"),
+            );
+    }
+}
+
+struct Inspector;
+
+fn inspector_spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(Inspector);
+}
+
+fn inspector_select(
+    mouse: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_position: Res<MousePosition>,
+    query: Query<(Entity, &Transform), With<Physics>>,
+    mut selection: ResMut<Selection>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        selection.entity = None;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F) {
+        selection.follow = !selection.follow;
+    }
+
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+
+    selection.entity = query
+        .iter()
+        .map(|(entity, transform)| (entity, dist(transform.translation, mouse_position.value)))
+        .filter(|(_, ds)| *ds < SELECT_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+}
+
+fn inspector_edit(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut selection: ResMut<Selection>,
+    mut query: Query<(&mut Physics, &mut MovementSpeed, Option<&mut WolfBehavior>)>,
+    clock: Res<SimulationClock>,
+) {
+    let entity = match selection.entity {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        selection.field = (selection.field + FIELDS.len() - 1) % FIELDS.len();
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        selection.field = (selection.field + 1) % FIELDS.len();
+    }
+
+    let mut change = 0.0;
+
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        change -= 1.0;
+    }
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        change += 1.0;
+    }
+
+    if change == 0.0 {
+        return;
+    }
+
+    if let Ok((mut physics, mut speed, behavior)) = query.get_mut(entity) {
+        let field = FIELDS[selection.field];
+        let change = change * field.step();
+
+        match field {
+            InspectorField::VelocityX => physics.velocity.x += change,
+            InspectorField::VelocityY => physics.velocity.y += change,
+            InspectorField::WanderTheta => physics.wander_theta += change,
+            InspectorField::MovementSpeed => speed.value = (speed.value + change).max(0.0),
+            InspectorField::Hunger => {
+                if let Some(mut behavior) = behavior {
+                    let hunger = behavior.hunger(clock.elapsed) + change;
+                    behavior.set_hunger(clock.elapsed, hunger);
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn inspector_update(
+    mut selection: ResMut<Selection>,
+    query: Query<(
+        &Physics,
+        &MovementSpeed,
+        Option<&Health>,
+        Option<&WolfBehavior>,
+        Option<&SteeringDebug>,
+        Option<&PackID>,
+        Option<&GroupID>,
+        Option<&Target>,
+    )>,
+    species_query: Query<(Option<&Player>, Option<&Hare>, Option<&Wolf>, Option<&Deer>)>,
+    mut text_query: Query<(&mut Text, &mut Visible), With<Inspector>>,
+    clock: Res<SimulationClock>,
+) {
+    let entity = selection.entity;
+    let selected = entity.and_then(|entity| query.get(entity).ok());
+
+    if selected.is_none() {
+        selection.entity = None;
+    }
+
+    for (mut text, mut visible) in text_query.iter_mut() {
+        visible.is_visible = selected.is_some();
+
+        let (physics, speed, health, behavior, steering, pack, group, target) = match selected {
+            Some(selected) => selected,
+            None => continue,
+        };
+
+        let species = match species_query.get(entity.unwrap()) {
+            Ok((Some(_), _, _, _)) => "Player",
+            Ok((_, Some(_), _, _)) => "Hare",
+            Ok((_, _, Some(_), _)) => "Wolf",
+            Ok((_, _, _, Some(_))) => "Deer",
+            _ => "Entity",
+        };
+
+        let values = [
+            format!("Velocity x: {:.2}", physics.velocity.x),
+            format!("Velocity y: {:.2}", physics.velocity.y),
+            format!("Wander theta: {:.2}", physics.wander_theta),
+            format!("Movement speed: {:.0}", speed.value),
+            match behavior {
+                Some(behavior) => format!("Hunger: {:.0}%", behavior.hunger(clock.elapsed) * 100.0),
+                None => "Hunger: -".to_string(),
+            },
+        ];
+
+        let mut lines = vec![format!("{} {:?}", species, entity.unwrap())];
+
+        for (i, value) in values.iter().enumerate() {
+            let marker = if i == selection.field { ">" } else { " " };
+            lines.push(format!("{} {}", marker, value));
+        }
+
+        lines.push(format!(
+            "Acceleration: ({:.3}, {:.3})",
+            physics.acceleration.x, physics.acceleration.y
+        ));
+
+        if let Some(steering) = steering {
+            let force = steering.total();
+            lines.push(format!("Force: ({:.3}, {:.3})", force.x, force.y));
+        }
+        if let Some(health) = health {
+            lines.push(format!("Health: {:.0}/{:.0}", health.value, health.max));
+        }
+        if let Some(pack) = pack {
+            lines.push(format!("Pack: {}", pack.value));
+        }
+        if let Some(group) = group {
+            lines.push(format!("Group: {}", group.value));
+        }
+        if let Some(target) = target {
+            let target = match target.entity {
+                Some(target) => format!("{:?}", target),
+                None => "-".to_string(),
+            };
+            lines.push(format!("Target: {}", target));
+        }
+
+        lines.push(format!(
+            "Up/Down - field, Left/Right - edit, F - follow ({}), Esc - close",
+            if selection.follow { "on" } else { "off" }
+        ));
+
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,455 @@
+mod bindings_menu;
+mod camera;
+mod components;
+mod controls;
+mod debug;
+mod deer;
+mod editor;
+pub mod geometry;
+mod hare;
+mod hud;
+mod inspector;
+mod map;
+mod metrics;
+mod pause_menu;
+mod perception;
+mod player;
+mod settings;
+mod simulation;
+mod snapshot;
+mod stats;
+mod steering;
+mod targeting;
+#[cfg(test)]
+mod test_utils;
+mod tuning;
+mod utils;
+mod wariness;
+mod wolf;
+
+use bevy::{
+    ecs::component::Component,
+    input::InputPlugin,
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPoolBuilder},
+    window::{WindowFocused, WindowPlugin, WindowResized},
+};
+use bindings_menu::BindingsMenuPlugin;
+use camera::CameraPlugin;
+use controls::ActionState;
+use controls::ControlsPlugin;
+use debug::DebugPlugin;
+use deer::{Deer, DeerPlugin};
+use editor::EditorPlugin;
+use geometry::{ray_circle, ray_segment, Hit};
+use hare::{Hare, HarePlugin};
+use hud::HudPlugin;
+use inspector::InspectorPlugin;
+use map::{MapLayout, MapPlugin, Obstacle};
+use metrics::MetricsPlugin;
+use pause_menu::PauseMenuPlugin;
+use perception::PerceptionPlugin;
+use serde_json::Value;
+use simulation::{SimRng, SimulationClock, SimulationPlugin};
+use snapshot::SnapshotPlugin;
+use stats::StatsPlugin;
+use std::io;
+use tuning::TuningPlugin;
+use wariness::WarinessPlugin;
+use wolf::{Wolf, WolfPlugin};
+
+use crate::components::{MainCamera, Materials, MousePosition};
+use crate::player::{GunshotEvent, Player, PlayerPlugin};
+use crate::settings::get_f32;
+
+pub use settings::{write_json, Settings, SETTINGS_PATH};
+pub use stats::{DeathCause, DeathEvent, SimulationStats, Species};
+
+const TIME_STEP: f32 = 1.0 / 60.0;
+const FONT_PATH: &str = "fonts/DejaVuSans.ttf";
+
+struct FieldSize {
+    width: f32,
+    height: f32,
+}
+
+#[derive(Clone, Copy)]
+struct WallData {
+    point_a: Vec3,
+    point_b: Vec3,
+}
+
+struct Walls {
+    value: Vec<WallData>,
+    obstacles: Vec<Obstacle>,
+}
+
+impl Walls {
+    fn ray_hits(&self, origin: Vec2, direction: Vec2) -> impl Iterator<Item = Hit> + '_ {
+        let wall_hits = self.value.iter().filter_map(move |wall| {
+            ray_segment(
+                origin,
+                direction,
+                wall.point_a.truncate(),
+                wall.point_b.truncate(),
+            )
+        });
+        let obstacle_hits = self.obstacles.iter().filter_map(move |obstacle| {
+            ray_circle(
+                origin,
+                direction,
+                obstacle.position.truncate(),
+                obstacle.radius,
+            )
+        });
+
+        wall_hits.chain(obstacle_hits)
+    }
+}
+
+#[derive(Default)]
+pub struct AppOptions {
+    pub headless: bool,
+    pub snapshot: Option<String>,
+    pub metrics: Option<String>,
+}
+
+pub fn build_app(app: &mut AppBuilder, settings: Settings, options: AppOptions) {
+    let headless = options.headless;
+    let clock = settings::simulation_clock(&settings.value, headless);
+    let rng = settings::sim_rng(&settings.value);
+    let snapshot = settings::snapshot_data(&settings.value, options.snapshot);
+    let metrics = settings::metrics_data(&settings.value, options.metrics);
+
+    app.insert_resource(WindowDescriptor {
+        title: "Hunter Game".to_string(),
+        width: 1280.0,
+        height: 720.0,
+        ..Default::default()
+    })
+    .insert_resource(MousePosition::default())
+    .insert_resource(settings)
+    .insert_resource(clock)
+    .insert_resource(rng)
+    .insert_resource(snapshot)
+    .insert_resource(metrics);
+
+    if headless {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(WindowPlugin {
+                add_primary_window: false,
+                exit_on_close: false,
+            })
+            .add_plugin(InputPlugin)
+            .insert_resource(Materials::default());
+    } else {
+        app.insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
+            .add_plugins(DefaultPlugins)
+            .add_startup_system(setup_graphics.system())
+            .add_plugin(CameraPlugin)
+            .add_plugin(PauseMenuPlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(DebugPlugin)
+            .add_plugin(InspectorPlugin)
+            .add_plugin(TuningPlugin)
+            .add_plugin(BindingsMenuPlugin)
+            .add_plugin(EditorPlugin);
+    }
+
+    app.add_startup_system(setup.system())
+        .add_plugin(SimulationPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(MetricsPlugin)
+        .add_plugin(MapPlugin)
+        .add_plugin(PerceptionPlugin)
+        .add_plugin(WarinessPlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(HarePlugin)
+        .add_plugin(WolfPlugin)
+        .add_plugin(DeerPlugin)
+        .add_plugin(SnapshotPlugin);
+}
+
+pub fn step(app: &mut App) {
+    let mut clock = app.world.get_resource_mut::<SimulationClock>().unwrap();
+    clock.paused = true;
+    clock.step();
+
+    app.update();
+}
+
+pub fn elapsed(app: &App) -> f32 {
+    app.world.get_resource::<SimulationClock>().unwrap().elapsed
+}
+
+pub fn species_app(settings: Settings, seed: u64, species: Species) -> App {
+    let value = &settings.value;
+    let FieldSize { width, height } = field_size(value, &WindowDescriptor::default());
+
+    let mut builder = App::build();
+    builder
+        .insert_resource(ComputeTaskPool(
+            TaskPoolBuilder::new().num_threads(1).build(),
+        ))
+        .insert_resource(Time::default())
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<Input<MouseButton>>()
+        .init_resource::<ActionState>()
+        .add_event::<WindowFocused>()
+        .add_event::<WindowResized>()
+        .add_event::<GunshotEvent>()
+        .insert_resource(MousePosition::default())
+        .insert_resource(Materials::default())
+        .insert_resource(SimRng::new(seed))
+        .insert_resource(settings::player_data(value))
+        .insert_resource(settings::hare_data(value))
+        .insert_resource(settings::wolf_data(value))
+        .insert_resource(settings::deer_data(value))
+        .insert_resource(settings::bullet_data(value))
+        .insert_resource(settings::score_data(value))
+        .insert_resource(settings::hare_steering_data(value))
+        .insert_resource(settings::wolf_steering_data(value))
+        .insert_resource(settings::deer_steering_data(value))
+        .insert_resource(settings::map_data(value))
+        .insert_resource(FieldSize { width, height })
+        .insert_resource(Walls {
+            value: boundary_walls(width, height),
+            obstacles: Vec::new(),
+        })
+        .insert_resource(MapLayout::default())
+        .insert_resource(settings)
+        .add_plugin(SimulationPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(MapPlugin)
+        .add_plugin(PerceptionPlugin)
+        .add_plugin(WarinessPlugin);
+
+    match species {
+        Species::Player => panic!("the player cannot be simulated on its own"),
+        Species::Hare => builder.add_plugin(HarePlugin),
+        Species::Wolf => builder.add_plugin(WolfPlugin),
+        Species::Deer => builder.add_plugin(DeerPlugin),
+    };
+
+    builder.app
+}
+
+pub fn positions(app: &mut App, species: Species) -> Vec<Vec3> {
+    let mut entities: Vec<(Entity, Vec3)> = match species {
+        Species::Player => entity_positions::<Player>(app),
+        Species::Hare => entity_positions::<Hare>(app),
+        Species::Wolf => entity_positions::<Wolf>(app),
+        Species::Deer => entity_positions::<Deer>(app),
+    };
+    entities.sort_by_key(|(entity, _)| entity.id());
+
+    entities.into_iter().map(|(_, position)| position).collect()
+}
+
+fn entity_positions<T: Component>(app: &mut App) -> Vec<(Entity, Vec3)> {
+    app.world
+        .query_filtered::<(Entity, &Transform), With<T>>()
+        .iter(&app.world)
+        .map(|(entity, transform)| (entity, transform.translation))
+        .collect()
+}
+
+fn setup(mut commands: Commands, window: Res<WindowDescriptor>, settings: Res<Settings>) {
+    let settings = &settings.value;
+
+    commands.insert_resource(settings::player_data(settings));
+    commands.insert_resource(settings::hare_data(settings));
+    commands.insert_resource(settings::wolf_data(settings));
+    commands.insert_resource(settings::deer_data(settings));
+    commands.insert_resource(settings::bullet_data(settings));
+    commands.insert_resource(settings::camera_data(settings));
+    commands.insert_resource(settings::controls_data(settings));
+    commands.insert_resource(settings::input_map(settings));
+    commands.insert_resource(settings::score_data(settings));
+
+    commands.insert_resource(settings::hare_steering_data(settings));
+    commands.insert_resource(settings::wolf_steering_data(settings));
+    commands.insert_resource(settings::deer_steering_data(settings));
+
+    let FieldSize { width, height } = field_size(settings, &window);
+
+    commands.insert_resource(FieldSize { width, height });
+
+    commands.insert_resource(Walls {
+        value: boundary_walls(width, height),
+        obstacles: Vec::new(),
+    });
+
+    let map_data = settings::map_data(settings);
+
+    let layout = match MapLayout::load(&map_data.path) {
+        Ok(layout) => layout,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => MapLayout::default(),
+        Err(error) => {
+            warn!("Failed to load {}: {}", map_data.path, error);
+            MapLayout::default()
+        }
+    };
+
+    commands.insert_resource(layout);
+    commands.insert_resource(map_data);
+}
+
+fn boundary_walls(width: f32, height: f32) -> Vec<WallData> {
+    vec![
+        WallData {
+            point_a: Vec3::new(width / 2.0, height / 2.0, 0.0),
+            point_b: Vec3::new(width / 2.0, -(height / 2.0), 0.0),
+        },
+        WallData {
+            point_a: Vec3::new(-(width / 2.0), -(height / 2.0), 0.0),
+            point_b: Vec3::new(width / 2.0, -(height / 2.0), 0.0),
+        },
+        WallData {
+            point_a: Vec3::new(-(width / 2.0), height / 2.0, 0.0),
+            point_b: Vec3::new(-(width / 2.0), -(height / 2.0), 0.0),
+        },
+        WallData {
+            point_a: Vec3::new(-(width / 2.0), height / 2.0, 0.0),
+            point_b: Vec3::new(width / 2.0, height / 2.0, 0.0),
+        },
+    ]
+}
+
+fn setup_graphics(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    window: Res<WindowDescriptor>,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    let settings = &settings.value;
+
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(MainCamera);
+    commands.spawn_bundle(UiCameraBundle::default());
+
+    commands.insert_resource(Materials {
+        player_material: materials.add(ColorMaterial {
+            color: Color::rgb(
+                settings["player"]["material"]["color"]["r"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["player"]["material"]["color"]["g"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["player"]["material"]["color"]["b"]
+                    .as_f64()
+                    .unwrap() as f32,
+            ),
+            texture: asset_server
+                .load(settings["player"]["material"]["texture"].as_str().unwrap())
+                .into(),
+        }),
+        hare_material: materials.add(ColorMaterial {
+            texture: asset_server
+                .load(settings["hare"]["material"]["texture"].as_str().unwrap())
+                .into(),
+            ..Default::default()
+        }),
+        wolf_material: materials.add(ColorMaterial {
+            color: Color::rgb(
+                settings["wolf"]["material"]["color"]["r"].as_f64().unwrap() as f32,
+                settings["wolf"]["material"]["color"]["g"].as_f64().unwrap() as f32,
+                settings["wolf"]["material"]["color"]["b"].as_f64().unwrap() as f32,
+            ),
+            texture: asset_server
+                .load(settings["wolf"]["material"]["texture"].as_str().unwrap())
+                .into(),
+        }),
+        deer_material: materials.add(ColorMaterial {
+            color: Color::rgb(
+                settings["deer"]["material"]["color"]["r"].as_f64().unwrap() as f32,
+                settings["deer"]["material"]["color"]["g"].as_f64().unwrap() as f32,
+                settings["deer"]["material"]["color"]["b"].as_f64().unwrap() as f32,
+            ),
+            texture: asset_server
+                .load(settings["deer"]["material"]["texture"].as_str().unwrap())
+                .into(),
+        }),
+        bullet_material: materials.add(ColorMaterial {
+            texture: asset_server
+                .load(settings["bullet"]["material"]["texture"].as_str().unwrap())
+                .into(),
+            ..Default::default()
+        }),
+        burrow_material: materials.add(
+            Color::rgb(
+                settings["hare"]["burrow"]["material"]["color"]["r"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["hare"]["burrow"]["material"]["color"]["g"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["hare"]["burrow"]["material"]["color"]["b"]
+                    .as_f64()
+                    .unwrap() as f32,
+            )
+            .into(),
+        ),
+        den_material: materials.add(
+            Color::rgb(
+                settings["wolf"]["den"]["material"]["color"]["r"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["wolf"]["den"]["material"]["color"]["g"]
+                    .as_f64()
+                    .unwrap() as f32,
+                settings["wolf"]["den"]["material"]["color"]["b"]
+                    .as_f64()
+                    .unwrap() as f32,
+            )
+            .into(),
+        ),
+        wall_material: materials.add(
+            settings::get_color(
+                &settings["map"]["materials"]["wall"],
+                Color::rgb(0.3, 0.25, 0.2),
+            )
+            .into(),
+        ),
+        obstacle_material: materials.add(
+            settings::get_color(
+                &settings["map"]["materials"]["obstacle"],
+                Color::rgb(0.35, 0.35, 0.35),
+            )
+            .into(),
+        ),
+        food_material: materials.add(
+            settings::get_color(
+                &settings["map"]["materials"]["food"],
+                Color::rgb(0.5, 0.8, 0.2),
+            )
+            .into(),
+        ),
+        region_material: materials.add(
+            settings::get_color(
+                &settings["map"]["materials"]["region"],
+                Color::rgba(1.0, 1.0, 1.0, 0.1),
+            )
+            .into(),
+        ),
+    });
+
+    let FieldSize { width, height } = field_size(settings, &window);
+
+    commands.spawn_bundle(SpriteBundle {
+        material: materials.add(Color::rgba(0.1, 0.7, 0.2, 1.0).into()),
+        sprite: Sprite::new(Vec2::new(width, height)),
+        ..Default::default()
+    });
+}
+
+fn field_size(settings: &Value, window: &WindowDescriptor) -> FieldSize {
+    FieldSize {
+        width: get_f32(&settings["world"]["width"], window.width - 80.0),
+        height: get_f32(&settings["world"]["height"], window.height - 20.0),
+    }
+}
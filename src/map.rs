@@ -0,0 +1,315 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde_json::{from_str, json, Value};
+use std::{fs, io};
+
+use crate::{
+    boundary_walls,
+    components::Materials,
+    hare::{Burrow, HareData},
+    settings::{get_f32, write_json},
+    stats::Species,
+    wolf::WolfData,
+    FieldSize, WallData, Walls,
+};
+
+pub struct MapData {
+    pub path: String,
+    pub obstacle_radius: f32,
+    pub food_radius: f32,
+    pub wall_width: f32,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Obstacle {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct FoodPatch {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct SpawnRegion {
+    pub species: Species,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl SpawnRegion {
+    pub fn new(species: Species, a: Vec3, b: Vec3) -> Self {
+        Self {
+            species,
+            min: a.truncate().min(b.truncate()),
+            max: a.truncate().max(b.truncate()),
+        }
+    }
+
+    pub fn contains(&self, position: Vec3) -> bool {
+        position.x >= self.min.x
+            && position.x <= self.max.x
+            && position.y >= self.min.y
+            && position.y <= self.max.y
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MapLayout {
+    pub walls: Vec<WallData>,
+    pub obstacles: Vec<Obstacle>,
+    pub burrows: Vec<Vec3>,
+    pub dens: Vec<Vec3>,
+    pub food_patches: Vec<FoodPatch>,
+    pub spawn_regions: Vec<SpawnRegion>,
+}
+
+impl MapLayout {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let map: Value = from_str(contents.as_str())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        Ok(Self::from_value(&map))
+    }
+
+    pub fn from_value(map: &Value) -> Self {
+        let list = |key: &str| map[key].as_array().cloned().unwrap_or_default();
+
+        Self {
+            walls: list("walls")
+                .iter()
+                .map(|wall| WallData {
+                    point_a: get_position(&wall["a"]),
+                    point_b: get_position(&wall["b"]),
+                })
+                .collect(),
+            obstacles: list("obstacles")
+                .iter()
+                .map(|obstacle| Obstacle {
+                    position: get_position(obstacle),
+                    radius: get_f32(&obstacle["radius"], 30.0),
+                })
+                .collect(),
+            burrows: list("burrows").iter().map(get_position).collect(),
+            dens: list("dens").iter().map(get_position).collect(),
+            food_patches: list("food_patches")
+                .iter()
+                .map(|food_patch| FoodPatch {
+                    position: get_position(food_patch),
+                    radius: get_f32(&food_patch["radius"], 60.0),
+                })
+                .collect(),
+            spawn_regions: list("spawn_regions")
+                .iter()
+                .filter_map(|region| {
+                    Species::from_name(region["species"].as_str()?).map(|species| SpawnRegion {
+                        species,
+                        min: get_position(&region["min"]).truncate(),
+                        max: get_position(&region["max"]).truncate(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        write_json(path, &self.to_value())
+    }
+
+    pub fn to_value(&self) -> Value {
+        let position = |position: Vec3| json!({ "x": position.x, "y": position.y });
+
+        json!({
+            "walls": self.walls.iter().map(|wall| json!({
+                "a": position(wall.point_a),
+                "b": position(wall.point_b),
+            })).collect::<Vec<_>>(),
+            "obstacles": self.obstacles.iter().map(|obstacle| json!({
+                "x": obstacle.position.x,
+                "y": obstacle.position.y,
+                "radius": obstacle.radius,
+            })).collect::<Vec<_>>(),
+            "burrows": self.burrows.iter().map(|burrow| position(*burrow)).collect::<Vec<_>>(),
+            "dens": self.dens.iter().map(|den| position(*den)).collect::<Vec<_>>(),
+            "food_patches": self.food_patches.iter().map(|food_patch| json!({
+                "x": food_patch.position.x,
+                "y": food_patch.position.y,
+                "radius": food_patch.radius,
+            })).collect::<Vec<_>>(),
+            "spawn_regions": self.spawn_regions.iter().map(|region| json!({
+                "species": region.species.name(),
+                "min": position(region.min.extend(0.0)),
+                "max": position(region.max.extend(0.0)),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn spawn_point(
+        &self,
+        species: Species,
+        field_size: &FieldSize,
+        margin: f32,
+        rng: &mut impl Rng,
+    ) -> Vec3 {
+        let half_field = Vec2::new(field_size.width, field_size.height) / 2.0 - Vec2::splat(margin);
+        let regions: Vec<&SpawnRegion> = self
+            .spawn_regions
+            .iter()
+            .filter(|region| region.species == species)
+            .collect();
+
+        let (min, max) = if regions.is_empty() {
+            (-half_field, half_field)
+        } else {
+            let region = regions[rng.gen_range(0..regions.len())];
+            (
+                region.min.max(-half_field).min(half_field),
+                region.max.max(-half_field).min(half_field),
+            )
+        };
+
+        let x = if min.x < max.x {
+            rng.gen_range(min.x..max.x)
+        } else {
+            min.x
+        };
+        let y = if min.y < max.y {
+            rng.gen_range(min.y..max.y)
+        } else {
+            min.y
+        };
+
+        Vec3::new(x, y, 0.0)
+    }
+}
+
+pub(crate) struct MapFeature;
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(map_sync.system().label("map_sync"));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn map_sync(
+    mut commands: Commands,
+    layout: Res<MapLayout>,
+    feature_query: Query<Entity, With<MapFeature>>,
+    mut walls: ResMut<Walls>,
+    field_size: Res<FieldSize>,
+    materials: Res<Materials>,
+    map_data: Res<MapData>,
+    hare_data: Res<HareData>,
+    wolf_data: Res<WolfData>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+
+    for feature in feature_query.iter() {
+        commands.entity(feature).despawn();
+    }
+
+    walls.value = boundary_walls(field_size.width, field_size.height);
+    walls.obstacles = layout.obstacles.clone();
+
+    for wall in layout.walls.iter() {
+        walls.value.push(*wall);
+
+        commands
+            .spawn_bundle(wall_sprite(
+                materials.wall_material.clone(),
+                wall.point_a,
+                wall.point_b,
+                map_data.wall_width,
+            ))
+            .insert(MapFeature);
+    }
+
+    for obstacle in layout.obstacles.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.obstacle_material.clone(),
+                sprite: Sprite::new(Vec2::splat(obstacle.radius * 2.0)),
+                transform: Transform::from_xyz(obstacle.position.x, obstacle.position.y, 0.2),
+                ..Default::default()
+            })
+            .insert(MapFeature);
+    }
+
+    for burrow in layout.burrows.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.burrow_material.clone(),
+                sprite: Sprite::new(Vec2::splat(hare_data.burrow.radius * 2.0)),
+                transform: Transform::from_xyz(burrow.x, burrow.y, 0.1),
+                ..Default::default()
+            })
+            .insert(Burrow {
+                radius: hare_data.burrow.radius,
+            })
+            .insert(MapFeature);
+    }
+
+    for den in layout.dens.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.den_material.clone(),
+                sprite: Sprite::new(Vec2::splat(wolf_data.den.size)),
+                transform: Transform::from_xyz(den.x, den.y, 0.05),
+                ..Default::default()
+            })
+            .insert(MapFeature);
+    }
+
+    for food_patch in layout.food_patches.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.food_material.clone(),
+                sprite: Sprite::new(Vec2::splat(food_patch.radius * 2.0)),
+                transform: Transform::from_xyz(food_patch.position.x, food_patch.position.y, 0.05),
+                ..Default::default()
+            })
+            .insert(*food_patch)
+            .insert(MapFeature);
+    }
+
+    for region in layout.spawn_regions.iter() {
+        let center = (region.min + region.max) / 2.0;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.region_material.clone(),
+                sprite: Sprite::new(region.max - region.min),
+                transform: Transform::from_xyz(center.x, center.y, 0.01),
+                ..Default::default()
+            })
+            .insert(MapFeature);
+    }
+}
+
+fn wall_sprite(material: Handle<ColorMaterial>, from: Vec3, to: Vec3, width: f32) -> SpriteBundle {
+    let middle = (from + to) / 2.0;
+    let angle = (to.y - from.y).atan2(to.x - from.x);
+
+    SpriteBundle {
+        material,
+        sprite: Sprite::new(Vec2::new((to - from).truncate().length(), width)),
+        transform: Transform {
+            translation: Vec3::new(middle.x, middle.y, 0.2),
+            rotation: Quat::from_rotation_z(angle),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn get_position(value: &Value) -> Vec3 {
+    Vec3::new(get_f32(&value["x"], 0.0), get_f32(&value["y"], 0.0), 0.0)
+}
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Write},
+};
+
+use crate::{
+    deer::{Deer, GroupID},
+    hare::Hare,
+    player::{GunshotEvent, Player},
+    simulation::{SimulationClock, SIMULATION},
+    stats::{BirthEvent, DeathCause, DeathEvent, Score, Species},
+    steering::Physics,
+    wolf::{Wolf, WolfBehavior},
+    TIME_STEP,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MetricsFormat {
+    Csv,
+    Json,
+}
+
+pub struct MetricsData {
+    pub enabled: bool,
+    pub path: String,
+    pub format: MetricsFormat,
+    pub interval: f32,
+}
+
+#[derive(Default)]
+struct MetricsRecorder {
+    file: Option<File>,
+    header_written: bool,
+    next_sample: f32,
+    births: [u32; 4],
+    deaths: [[u32; 3]; 4],
+    shots: u32,
+}
+
+impl MetricsRecorder {
+    fn write(&mut self, format: MetricsFormat, sample: Vec<(String, Value)>) -> io::Result<()> {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        let mut lines = String::new();
+
+        match format {
+            MetricsFormat::Csv => {
+                if !self.header_written {
+                    let names: Vec<&str> = sample.iter().map(|(name, _)| name.as_str()).collect();
+                    lines.push_str(&names.join(","));
+                    lines.push('\n');
+                    self.header_written = true;
+                }
+
+                let values: Vec<String> =
+                    sample.iter().map(|(_, value)| csv_value(value)).collect();
+                lines.push_str(&values.join(","));
+            }
+            MetricsFormat::Json => {
+                let object: Map<String, Value> = sample.into_iter().collect();
+                lines.push_str(&Value::Object(object).to_string());
+            }
+        }
+
+        lines.push('\n');
+        file.write_all(lines.as_bytes())
+    }
+}
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<MetricsRecorder>()
+            .add_startup_system(metrics_open.system())
+            .add_system_to_stage(
+                SIMULATION,
+                metrics_count
+                    .system()
+                    .label("metrics_count")
+                    .after("player_shoot")
+                    .after("player_die")
+                    .after("hare_spawn")
+                    .after("hare_die")
+                    .after("wolf_spawn")
+                    .after("wolf_starve")
+                    .after("wolf_die")
+                    .after("deer_spawn")
+                    .after("deer_die"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                metrics_sample
+                    .system()
+                    .label("metrics")
+                    .after("metrics_count"),
+            );
+    }
+}
+
+fn metrics_open(metrics_data: Res<MetricsData>, mut recorder: ResMut<MetricsRecorder>) {
+    if !metrics_data.enabled {
+        return;
+    }
+
+    match File::create(&metrics_data.path) {
+        Ok(file) => {
+            info!("Writing metrics to {}", metrics_data.path);
+            recorder.file = Some(file);
+        }
+        Err(error) => warn!(
+            "Failed to open metrics file {}: {}",
+            metrics_data.path, error
+        ),
+    }
+}
+
+fn metrics_count(
+    mut recorder: ResMut<MetricsRecorder>,
+    mut births: EventReader<BirthEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    mut gunshots: EventReader<GunshotEvent>,
+) {
+    for birth in births.iter() {
+        recorder.births[birth.species.index()] += 1;
+    }
+    for death in deaths.iter() {
+        recorder.deaths[death.species.index()][death.cause.index()] += 1;
+    }
+
+    recorder.shots += gunshots.iter().count() as u32;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn metrics_sample(
+    mut recorder: ResMut<MetricsRecorder>,
+    metrics_data: Res<MetricsData>,
+    clock: Res<SimulationClock>,
+    score: Res<Score>,
+    player_query: Query<&Physics, With<Player>>,
+    hare_query: Query<&Physics, With<Hare>>,
+    wolf_query: Query<(&Physics, &WolfBehavior), With<Wolf>>,
+    deer_query: Query<(&Physics, &GroupID), With<Deer>>,
+) {
+    if recorder.file.is_none() || clock.elapsed < recorder.next_sample {
+        return;
+    }
+
+    recorder.next_sample = clock.elapsed + metrics_data.interval.max(TIME_STEP) - TIME_STEP / 2.0;
+
+    let mut groups: HashMap<u32, u32> = HashMap::new();
+    for (_, group) in deer_query.iter() {
+        *groups.entry(group.value).or_insert(0) += 1;
+    }
+
+    let mut sample = vec![
+        ("time".to_string(), Value::from(clock.elapsed)),
+        ("hares".to_string(), Value::from(hare_query.iter().count())),
+        ("wolves".to_string(), Value::from(wolf_query.iter().count())),
+        ("deer".to_string(), Value::from(deer_query.iter().count())),
+    ];
+
+    for species in Species::ALL.iter().skip(1) {
+        let births = recorder.births[species.index()];
+        sample.push((format!("{}_births", species.name()), births.into()));
+    }
+
+    for species in Species::ALL.iter() {
+        for cause in DeathCause::ALL.iter() {
+            let deaths = recorder.deaths[species.index()][cause.index()];
+            sample.push((format!("{}_{}", species.name(), cause.key()), deaths.into()));
+        }
+    }
+
+    sample.extend(vec![
+        (
+            "hare_speed".to_string(),
+            average(hare_query.iter().map(speed)),
+        ),
+        (
+            "wolf_speed".to_string(),
+            average(wolf_query.iter().map(|(physics, _)| speed(physics))),
+        ),
+        (
+            "deer_speed".to_string(),
+            average(deer_query.iter().map(|(physics, _)| speed(physics))),
+        ),
+        (
+            "wolf_hunger".to_string(),
+            average(
+                wolf_query
+                    .iter()
+                    .map(|(_, behavior)| behavior.hunger(clock.elapsed)),
+            ),
+        ),
+        ("deer_groups".to_string(), Value::from(groups.len())),
+        (
+            "deer_group_size".to_string(),
+            average(groups.values().map(|count| *count as f32)),
+        ),
+        (
+            "player_alive".to_string(),
+            Value::from(player_query.single().is_ok()),
+        ),
+        (
+            "player_speed".to_string(),
+            player_query
+                .single()
+                .map(speed)
+                .map_or(Value::Null, Value::from),
+        ),
+        ("score".to_string(), Value::from(score.value)),
+        ("shots".to_string(), Value::from(recorder.shots)),
+    ]);
+
+    if let Err(error) = recorder.write(metrics_data.format, sample) {
+        warn!("Failed to write metrics: {}", error);
+        recorder.file = None;
+    }
+}
+
+fn speed(physics: &Physics) -> f32 {
+    physics.velocity.truncate().length() / TIME_STEP
+}
+
+fn average(values: impl Iterator<Item = f32>) -> Value {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+
+    if count == 0 {
+        Value::Null
+    } else {
+        Value::from(sum / count as f32)
+    }
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(value) => (*value as u32).to_string(),
+        Value::Number(number) => match number.as_f64() {
+            Some(number) if number.fract() != 0.0 => format!("{:.4}", number),
+            _ => number.to_string(),
+        },
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{Action, InputMap},
+    simulation::SimulationClock,
+    FONT_PATH,
+};
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(pause_menu_spawn.system())
+            .add_system(pause_menu_update.system().label("pause_menu"));
+    }
+}
+
+struct PauseMenu;
+
+fn pause_menu_spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(PauseMenu);
+}
+
+fn pause_menu_update(
+    clock: Res<SimulationClock>,
+    input_map: Res<InputMap>,
+    mut query: Query<(&mut Text, &mut Visible), With<PauseMenu>>,
+) {
+    for (mut text, mut visible) in query.iter_mut() {
+        visible.is_visible = clock.paused || clock.scale != 1.0;
+
+        let bindings = |action: Action| {
+            input_map
+                .bindings(action)
+                .iter()
+                .map(|binding| binding.to_string())
+                .collect::<Vec<String>>()
+                .join("/")
+        };
+
+        text.sections[0].value = if clock.paused {
+            format!(
+                "Paused\n{} - resume\n{} - step one frame\n{} {} - time scale ({:.1}x)",
+                bindings(Action::Pause),
+                bindings(Action::Step),
+                bindings(Action::SlowDown),
+                bindings(Action::SpeedUp),
+                clock.scale
+            )
+        } else {
+            format!("{:.1}x", clock.scale)
+        };
+    }
+}
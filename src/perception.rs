@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{Hidden, Prey, Threat},
+    geometry::{point_segment_distance, segment_segment},
+    player::{BulletData, GunshotEvent},
+    simulation::SIMULATION,
+    utils::dist,
+    Walls,
+};
+
+pub struct PerceptionData {
+    pub field_of_view: f32,
+    pub range: f32,
+    pub hearing_radius: f32,
+}
+
+pub(crate) struct Perception {
+    pub field_of_view: f32,
+    pub range: f32,
+    pub hearing_radius: f32,
+    pub perceived: Vec<Entity>,
+    pub gunshots: Vec<Vec3>,
+}
+
+impl Perception {
+    pub fn new(data: &PerceptionData) -> Self {
+        Self {
+            field_of_view: data.field_of_view.to_radians(),
+            range: data.range,
+            hearing_radius: data.hearing_radius,
+            perceived: Vec::new(),
+            gunshots: Vec::new(),
+        }
+    }
+}
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            SIMULATION,
+            perceive.system().label("perception").after("player_shoot"),
+        );
+    }
+}
+
+fn perceive(
+    mut perceiver_query: Query<(Entity, &Transform, &mut Perception)>,
+    target_query: Query<(Entity, &Transform), (Or<(With<Threat>, With<Prey>)>, Without<Hidden>)>,
+    mut gunshots: EventReader<GunshotEvent>,
+    bullet_data: Res<BulletData>,
+    walls: Res<Walls>,
+) {
+    let shots: Vec<(Entity, Vec3)> = gunshots
+        .iter()
+        .map(|shot| (shot.shooter, shot.position))
+        .collect();
+
+    for (entity, transform, mut perception) in perceiver_query.iter_mut() {
+        perception.perceived.clear();
+        perception.gunshots.clear();
+
+        let position = transform.translation;
+        let facing = transform.local_y();
+
+        for (other, other_transform) in target_query.iter() {
+            if other == entity {
+                continue;
+            }
+
+            let target = other_transform.translation;
+            let ds = dist(position, target);
+
+            let heard = ds < perception.hearing_radius;
+            let seen = ds < perception.range
+                && in_field_of_view(position, facing, target, perception.field_of_view)
+                && in_line_of_sight(position, target, &walls);
+
+            if heard || seen {
+                perception.perceived.push(other);
+            }
+        }
+
+        for (shooter, shot_position) in shots.iter() {
+            if *shooter == entity {
+                continue;
+            }
+
+            if dist(position, *shot_position) < perception.hearing_radius * bullet_data.loudness {
+                perception.gunshots.push(*shot_position);
+            }
+        }
+    }
+}
+
+fn in_field_of_view(position: Vec3, facing: Vec3, target: Vec3, field_of_view: f32) -> bool {
+    let mut to_target = target - position;
+    to_target.z = 0.0;
+
+    if to_target.length_squared() == 0.0 {
+        return true;
+    }
+
+    facing.dot(to_target.normalize()) >= (field_of_view / 2.0).cos()
+}
+
+fn in_line_of_sight(position: Vec3, target: Vec3, walls: &Walls) -> bool {
+    !walls.value.iter().any(|wall| {
+        segment_segment(
+            position.truncate(),
+            target.truncate(),
+            wall.point_a.truncate(),
+            wall.point_b.truncate(),
+        )
+        .is_some()
+    }) && !walls.obstacles.iter().any(|obstacle| {
+        point_segment_distance(
+            obstacle.position.truncate(),
+            position.truncate(),
+            target.truncate(),
+        ) < obstacle.radius
+    })
+}
@@ -1,5 +1,6 @@
 use crate::{
     components::{Prey, Threat},
+    controls::{approach, Action, ActionState, PlayerInput},
     steering::Physics,
     wolf::{Wolf, WolfBehavior, WolfData},
     FieldSize,
@@ -8,10 +9,15 @@ use bevy::{
     prelude::*,
     sprite::collide_aabb::{collide, Collision},
 };
-use std::f32::consts::PI;
+use serde_json::{json, Value};
+use std::{f32::consts::PI, mem};
 
 use crate::{
     components::{Materials, MousePosition, MovementSpeed},
+    settings::get_f32,
+    simulation::{SimulationClock, SIMULATION},
+    snapshot::{get_physics, get_transform, physics_value, transform_value, EntityMap},
+    stats::{DeathCause, DeathEvent, Species},
     TIME_STEP,
 };
 
@@ -23,6 +29,41 @@ pub(crate) struct PlayerData {
     pub movement_speed: f32,
     pub width: f32,
     pub height: f32,
+    pub acceleration_time: f32,
+    pub deceleration_time: f32,
+    pub sprint_multiplier: f32,
+    pub stamina: f32,
+    pub stamina_drain: f32,
+    pub stamina_regen: f32,
+    pub stamina_recover: f32,
+}
+
+pub(crate) struct Stamina {
+    pub value: f32,
+    pub exhausted: bool,
+}
+
+impl Stamina {
+    pub fn sprint(&mut self, wants_sprint: bool, data: &PlayerData) -> bool {
+        let sprinting = wants_sprint && !self.exhausted;
+
+        if sprinting {
+            self.value -= data.stamina_drain * TIME_STEP;
+
+            if self.value <= 0.0 {
+                self.value = 0.0;
+                self.exhausted = true;
+            }
+        } else {
+            self.value = (self.value + data.stamina_regen * TIME_STEP).min(data.stamina);
+
+            if self.exhausted && self.value >= data.stamina_recover.min(data.stamina) {
+                self.exhausted = false;
+            }
+        }
+
+        sprinting
+    }
 }
 
 pub struct BulletData {
@@ -30,6 +71,13 @@ pub struct BulletData {
     pub height: f32,
     pub movement_speed: f32,
     pub max_duration: f32,
+    pub loudness: f32,
+    pub damage: f32,
+}
+
+pub struct GunshotEvent {
+    pub shooter: Entity,
+    pub position: Vec3,
 }
 
 pub struct BulletDuration {
@@ -44,21 +92,24 @@ impl Plugin for PlayerPlugin {
             "game_setup_player",
             SystemStage::single(player_spawn.system().label("player_spawn")),
         )
-        .add_system(player_move.system().label("player_movement"))
-        .add_system(player_rotate.system().label("player_rotation"))
-        .add_system(
+        .add_event::<GunshotEvent>()
+        .add_system_to_stage(SIMULATION, player_move.system().label("player_movement"))
+        .add_system_to_stage(SIMULATION, player_rotate.system().label("player_rotation"))
+        .add_system_to_stage(
+            SIMULATION,
             player_check_intersection
                 .system()
                 .label("player_intersection"),
         )
-        .add_system(player_shoot.system().label("player_shoot"))
-        .add_system(
+        .add_system_to_stage(SIMULATION, player_shoot.system().label("player_shoot"))
+        .add_system_to_stage(
+            SIMULATION,
             bullet_fly
                 .system()
                 .label("bullet_fly")
                 .after("player_shoot"),
         )
-        .add_system(player_die.system().label("player_die"));
+        .add_system_to_stage(SIMULATION, player_die.system().label("player_die"));
     }
 }
 
@@ -73,35 +124,46 @@ fn player_spawn(mut commands: Commands, materials: Res<Materials>, settings: Res
         .insert(Threat)
         .insert(Prey)
         .insert(MovementSpeed::new(settings.movement_speed))
+        .insert(Stamina {
+            value: settings.stamina,
+            exhausted: false,
+        })
         .insert(Physics {
-            velocity: Vec3::new(0.0, -2.0, 0.0),
+            velocity: Vec3::default(),
             acceleration: Vec3::default(),
             wander_theta: 0.0,
         });
 }
 
 fn player_move(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&MovementSpeed, &mut Transform, &mut Physics), With<Player>>,
+    input: Res<PlayerInput>,
+    actions: Res<ActionState>,
+    player_data: Res<PlayerData>,
+    mut query: Query<(&MovementSpeed, &mut Stamina, &mut Transform, &mut Physics), With<Player>>,
 ) {
-    if let Ok((speed, mut transform, mut physics)) = query.single_mut() {
-        let mut dir = Vec3::default();
+    if let Ok((speed, mut stamina, mut transform, mut physics)) = query.single_mut() {
+        let sprinting = actions.pressed(Action::Sprint) && input.movement != Vec2::ZERO;
+        let multiplier = if stamina.sprint(sprinting, &player_data) {
+            player_data.sprint_multiplier
+        } else {
+            1.0
+        };
 
-        if keyboard_input.pressed(KeyCode::A) {
-            dir.x = -1.0;
-        }
-        if keyboard_input.pressed(KeyCode::D) {
-            dir.x = 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::W) {
-            dir.y = 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::S) {
-            dir.y = -1.0;
-        }
+        let max_speed = speed.value * TIME_STEP;
+        let target = input.movement * max_speed * multiplier;
+        let current = physics.velocity.truncate();
+        let ramp_time = if target.length() > current.length() {
+            player_data.acceleration_time
+        } else {
+            player_data.deceleration_time
+        };
+        let max_delta = if ramp_time > 0.0 {
+            max_speed * TIME_STEP / ramp_time
+        } else {
+            f32::INFINITY
+        };
 
-        dir.normalize();
-        physics.velocity = dir * speed.value * TIME_STEP;
+        physics.velocity = approach(current, target, max_delta).extend(0.0);
         transform.translation += physics.velocity;
     }
 }
@@ -142,14 +204,17 @@ fn player_check_intersection(
 
 fn player_shoot(
     mut commands: Commands,
-    query: Query<&Transform, With<Player>>,
-    mouse: Res<Input<MouseButton>>,
+    query: Query<(Entity, &Transform), With<Player>>,
+    mut input: ResMut<PlayerInput>,
     materials: Res<Materials>,
     bullet_data: Res<BulletData>,
-    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    mut gunshots: EventWriter<GunshotEvent>,
 ) {
-    if let Ok(transform) = query.single() {
-        if mouse.just_released(MouseButton::Left) {
+    let fire = mem::take(&mut input.fire);
+
+    if let Ok((player, transform)) = query.single() {
+        if fire {
             commands
                 .spawn_bundle(SpriteBundle {
                     material: materials.bullet_material.clone(),
@@ -167,8 +232,13 @@ fn player_shoot(
                     wander_theta: 0.0,
                 })
                 .insert(BulletDuration {
-                    shot_at: time.seconds_since_startup() as f32,
+                    shot_at: clock.elapsed,
                 });
+
+            gunshots.send(GunshotEvent {
+                shooter: player,
+                position: transform.translation,
+            });
         }
     }
 }
@@ -177,11 +247,11 @@ fn bullet_fly(
     mut commands: Commands,
     mut query: Query<(&mut Transform, &Physics, &BulletDuration, Entity), With<Bullet>>,
     bullet_data: Res<BulletData>,
-    time: Res<Time>,
+    clock: Res<SimulationClock>,
 ) {
     for (mut transform, physics, duration, bullet) in query.iter_mut() {
-        let now = time.seconds_since_startup();
-        if now < (duration.shot_at + bullet_data.max_duration).into() {
+        let now = clock.elapsed;
+        if now < (duration.shot_at + bullet_data.max_duration) {
             transform.translation += physics.velocity;
         } else {
             commands.entity(bullet).despawn();
@@ -195,7 +265,8 @@ fn player_die(
     mut wolf_query: Query<(&Transform, &mut WolfBehavior), With<Wolf>>,
     player_data: Res<PlayerData>,
     wolf_data: Res<WolfData>,
-    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    mut deaths: EventWriter<DeathEvent>,
 ) {
     if let Ok((player, player_transform)) = player_query.single() {
         for (wolf_transform, mut behavior) in wolf_query.iter_mut() {
@@ -208,9 +279,91 @@ fn player_die(
             .is_some()
             {
                 commands.entity(player).despawn();
-                behavior.hunger_time = time.seconds_since_startup() as f32;
+                behavior.feed(clock.elapsed);
+                deaths.send(DeathEvent {
+                    species: Species::Player,
+                    cause: DeathCause::Eaten,
+                });
                 break;
             }
         }
     }
 }
+
+pub(crate) fn save_snapshot(world: &mut World) -> Value {
+    let players: Vec<Value> = world
+        .query_filtered::<(Entity, &Transform, &Physics, &MovementSpeed, &Stamina), With<Player>>()
+        .iter(world)
+        .map(|(player, transform, physics, speed, stamina)| {
+            json!({
+                "id": player.id(),
+                "transform": transform_value(transform),
+                "physics": physics_value(physics),
+                "movement_speed": speed.value,
+                "stamina": {
+                    "value": stamina.value,
+                    "exhausted": stamina.exhausted,
+                },
+            })
+        })
+        .collect();
+
+    let bullets: Vec<Value> = world
+        .query_filtered::<(Entity, &Transform, &Physics, &BulletDuration), With<Bullet>>()
+        .iter(world)
+        .map(|(bullet, transform, physics, duration)| {
+            json!({
+                "id": bullet.id(),
+                "transform": transform_value(transform),
+                "physics": physics_value(physics),
+                "shot_at": duration.shot_at,
+            })
+        })
+        .collect();
+
+    json!({
+        "entities": players,
+        "bullets": bullets,
+    })
+}
+
+pub(crate) fn load_snapshot(world: &mut World, snapshot: &Value, entities: &EntityMap) {
+    let materials = world.get_resource::<Materials>().unwrap();
+    let player_material = materials.player_material.clone();
+    let bullet_material = materials.bullet_material.clone();
+    let max_stamina = world.get_resource::<PlayerData>().unwrap().stamina;
+
+    for (player, record) in entities.records(&snapshot["entities"]) {
+        world
+            .entity_mut(player)
+            .insert_bundle(SpriteBundle {
+                material: player_material.clone(),
+                transform: get_transform(&record["transform"]),
+                ..Default::default()
+            })
+            .insert(Player)
+            .insert(Threat)
+            .insert(Prey)
+            .insert(MovementSpeed::new(get_f32(&record["movement_speed"], 0.0)))
+            .insert(Stamina {
+                value: get_f32(&record["stamina"]["value"], max_stamina),
+                exhausted: record["stamina"]["exhausted"].as_bool().unwrap_or(false),
+            })
+            .insert(get_physics(&record["physics"]));
+    }
+
+    for (bullet, record) in entities.records(&snapshot["bullets"]) {
+        world
+            .entity_mut(bullet)
+            .insert_bundle(SpriteBundle {
+                material: bullet_material.clone(),
+                transform: get_transform(&record["transform"]),
+                ..Default::default()
+            })
+            .insert(Bullet)
+            .insert(get_physics(&record["physics"]))
+            .insert(BulletDuration {
+                shot_at: get_f32(&record["shot_at"], 0.0),
+            });
+    }
+}
@@ -0,0 +1,457 @@
+use bevy::{
+    math::{Quat, Vec2, Vec3},
+    prelude::{Color, Transform},
+};
+use serde::Serialize;
+use serde_json::{from_str, ser::PrettyFormatter, Serializer, Value};
+use std::{fs, io};
+
+use crate::{
+    camera::CameraData,
+    controls::{ControlsData, InputMap},
+    deer::{AlarmData, DeerData, DeerSteeringData, HerdData},
+    hare::{BurrowData, HareData, HareSteeringData},
+    map::MapData,
+    metrics::{MetricsData, MetricsFormat},
+    perception::PerceptionData,
+    player::{BulletData, PlayerData},
+    simulation::{SimRng, SimulationClock},
+    snapshot::SnapshotData,
+    stats::ScoreData,
+    steering::{
+        EvadeData, EvadeWallsData, FlankData, FleeData, FlockingData, PursueData, SeekData,
+        WanderData,
+    },
+    targeting::TargetingData,
+    wariness::WarinessData,
+    wolf::{DenData, PackData, TerritoryData, WolfData, WolfSteeringData},
+};
+
+pub const SETTINGS_PATH: &str = "assets/settings.json";
+pub(crate) const MAP_PATH: &str = "assets/map.json";
+pub(crate) const SNAPSHOT_PATH: &str = "snapshot.json";
+pub(crate) const METRICS_PATH: &str = "metrics.csv";
+
+pub struct Settings {
+    pub value: Value,
+    pub path: String,
+}
+
+impl Settings {
+    pub fn load(path: &str) -> Self {
+        let contents = fs::read_to_string(path).expect("Something went wrong reading the file");
+
+        Self {
+            value: from_str(contents.as_str()).unwrap(),
+            path: path.to_string(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        write_json(&self.path, &self.value)
+    }
+}
+
+pub fn write_json(path: &str, value: &Value) -> io::Result<()> {
+    let mut contents = Vec::new();
+    let mut serializer =
+        Serializer::with_formatter(&mut contents, PrettyFormatter::with_indent(b"\t"));
+
+    value.serialize(&mut serializer)?;
+    contents.push(b'\n');
+
+    fs::write(path, contents)
+}
+
+pub fn get_f32(value: &Value, default: f32) -> f32 {
+    value.as_f64().map(|v| v as f32).unwrap_or(default)
+}
+
+pub fn get_u32(value: &Value, default: u32) -> u32 {
+    value.as_u64().map(|v| v as u32).unwrap_or(default)
+}
+
+pub fn get_color(value: &Value, default: Color) -> Color {
+    if value.is_null() {
+        return default;
+    }
+
+    Color::rgba(
+        get_f32(&value["r"], default.r()),
+        get_f32(&value["g"], default.g()),
+        get_f32(&value["b"], default.b()),
+        get_f32(&value["a"], default.a()),
+    )
+}
+
+pub fn player_data(settings: &Value) -> PlayerData {
+    let transform = get_transform(&settings["player"]["transform"]);
+    let sprite_size = get_f32(&settings["player"]["sprite_size"], 60.0);
+    let sprint = &settings["player"]["sprint"];
+    let stamina = get_f32(&sprint["stamina"], 3.0).max(0.0);
+
+    PlayerData {
+        transform,
+        movement_speed: settings["player"]["movement_speed"].as_f64().unwrap() as f32,
+        width: sprite_size * transform.scale.x,
+        height: sprite_size * transform.scale.y,
+        acceleration_time: get_f32(&settings["player"]["acceleration_time"], 0.15).max(0.0),
+        deceleration_time: get_f32(&settings["player"]["deceleration_time"], 0.1).max(0.0),
+        sprint_multiplier: get_f32(&sprint["multiplier"], 1.6).max(1.0),
+        stamina,
+        stamina_drain: get_f32(&sprint["drain"], 1.0).max(0.0),
+        stamina_regen: get_f32(&sprint["regen"], 0.5).max(0.0),
+        stamina_recover: get_f32(&sprint["recover"], 1.0).max(0.0),
+    }
+}
+
+pub fn input_map(settings: &Value) -> InputMap {
+    InputMap::from_value(&settings["controls"]["bindings"])
+}
+
+pub fn controls_data(settings: &Value) -> ControlsData {
+    ControlsData {
+        stick_dead_zone: get_f32(&settings["controls"]["gamepad"]["dead_zone"], 0.15)
+            .max(0.0)
+            .min(0.95),
+    }
+}
+
+pub fn bullet_data(settings: &Value) -> BulletData {
+    BulletData {
+        width: 24.0,
+        height: 24.0,
+        movement_speed: settings["bullet"]["movement_speed"].as_f64().unwrap() as f32,
+        max_duration: settings["bullet"]["max_duration"].as_f64().unwrap() as f32,
+        loudness: get_f32(&settings["bullet"]["loudness"], 6.0),
+        damage: get_f32(&settings["bullet"]["damage"], 1.0),
+    }
+}
+
+pub fn camera_data(settings: &Value) -> CameraData {
+    let camera = &settings["camera"];
+
+    CameraData {
+        dead_zone: Vec2::new(
+            get_f32(&camera["dead_zone"]["width"], 200.0),
+            get_f32(&camera["dead_zone"]["height"], 120.0),
+        ),
+        smoothing: get_f32(&camera["smoothing"], 5.0),
+        min_zoom: get_f32(&camera["zoom"]["min"], 0.5),
+        max_zoom: get_f32(&camera["zoom"]["max"], 3.0),
+        zoom_speed: get_f32(&camera["zoom"]["speed"], 0.1).max(0.0).min(0.9),
+        fit_on_resize: camera["fit_on_resize"].as_bool().unwrap_or(true),
+    }
+}
+
+pub fn simulation_clock(settings: &Value, headless: bool) -> SimulationClock {
+    let simulation = &settings["simulation"];
+    let scale = if headless {
+        get_f32(&simulation["headless_time_scale"], 10.0)
+    } else {
+        get_f32(&simulation["time_scale"], 1.0)
+    };
+
+    SimulationClock::new(scale, get_u32(&simulation["max_steps_per_frame"], 10))
+}
+
+pub fn map_data(settings: &Value) -> MapData {
+    let map = &settings["map"];
+
+    MapData {
+        path: map["path"].as_str().unwrap_or(MAP_PATH).to_string(),
+        obstacle_radius: get_f32(&map["obstacle_radius"], 30.0),
+        food_radius: get_f32(&map["food_radius"], 60.0),
+        wall_width: get_f32(&map["wall_width"], 6.0),
+    }
+}
+
+pub fn sim_rng(settings: &Value) -> SimRng {
+    let seed = settings["simulation"]["seed"]
+        .as_u64()
+        .unwrap_or_else(rand::random);
+
+    SimRng::new(seed)
+}
+
+pub fn snapshot_data(settings: &Value, path: Option<String>) -> SnapshotData {
+    SnapshotData {
+        load_on_start: path.is_some(),
+        path: path.unwrap_or_else(|| {
+            settings["snapshot"]["path"]
+                .as_str()
+                .unwrap_or(SNAPSHOT_PATH)
+                .to_string()
+        }),
+    }
+}
+
+pub fn metrics_data(settings: &Value, path: Option<String>) -> MetricsData {
+    let metrics = &settings["metrics"];
+    let format = match metrics["format"].as_str() {
+        Some("json") => MetricsFormat::Json,
+        _ => MetricsFormat::Csv,
+    };
+
+    MetricsData {
+        enabled: path.is_some() || metrics["enabled"].as_bool().unwrap_or(false),
+        path: path.unwrap_or_else(|| metrics["path"].as_str().unwrap_or(METRICS_PATH).to_string()),
+        format,
+        interval: get_f32(&metrics["interval"], 1.0),
+    }
+}
+
+pub fn score_data(settings: &Value) -> ScoreData {
+    ScoreData {
+        hare: get_u32(&settings["score"]["hare"], 1),
+        wolf: get_u32(&settings["score"]["wolf"], 3),
+        deer: get_u32(&settings["score"]["deer"], 2),
+    }
+}
+
+pub fn hare_data(settings: &Value) -> HareData {
+    let transform = get_transform(&settings["hare"]["transform"]);
+    let sprite_size = get_f32(&settings["hare"]["sprite_size"], 60.0);
+
+    HareData {
+        transform,
+        movement_speed: settings["hare"]["movement_speed"].as_f64().unwrap() as f32,
+        width: sprite_size * transform.scale.x,
+        height: sprite_size * transform.scale.y,
+        max_number: settings["hare"]["max_number"].as_u64().unwrap() as u32,
+        health: get_f32(&settings["hare"]["health"], 1.0),
+        spawn_margin: get_f32(&settings["hare"]["spawn_margin"], 30.0),
+        perception: get_perception(&settings["hare"]["perception"]),
+        burrow: BurrowData {
+            number: get_u32(&settings["hare"]["burrow"]["number"], 4),
+            radius: get_f32(&settings["hare"]["burrow"]["radius"], 20.0),
+            emerge_delay: get_f32(&settings["hare"]["burrow"]["emerge_delay"], 4.0),
+        },
+    }
+}
+
+pub fn hare_steering_data(settings: &Value) -> HareSteeringData {
+    let steering = &settings["hare"]["steering"];
+
+    HareSteeringData {
+        wander: get_wander(&steering["wander"]),
+        flee: FleeData {
+            weight: steering["flee"]["weight"].as_f64().unwrap() as f32,
+            max_flee_time: steering["flee"]["max_flee_time"].as_f64().unwrap() as f32,
+            radius: get_f32(&steering["flee"]["radius"], 100.0),
+            speed_boost: get_f32(&steering["flee"]["speed_boost"], 50.0),
+        },
+        evade_walls: get_evade_walls(&steering["evade_walls"]),
+        seek_burrow: SeekData {
+            weight: get_f32(&steering["seek_burrow"]["weight"], 0.3),
+        },
+        graze: SeekData {
+            weight: get_f32(&steering["graze"]["weight"], 0.0),
+        },
+    }
+}
+
+pub fn wolf_data(settings: &Value) -> WolfData {
+    let transform = get_transform(&settings["wolf"]["transform"]);
+    let sprite_size = get_f32(&settings["wolf"]["sprite_size"], 60.0);
+
+    WolfData {
+        transform,
+        movement_speed: settings["wolf"]["movement_speed"].as_f64().unwrap() as f32,
+        width: sprite_size * transform.scale.x,
+        height: sprite_size * transform.scale.y,
+        max_number: settings["wolf"]["max_number"].as_u64().unwrap() as u32,
+        max_hunger_time: get_f32(&settings["wolf"]["max_hunger_time"], 10.0),
+        health: get_f32(&settings["wolf"]["health"], 1.0),
+        spawn_margin: get_f32(&settings["wolf"]["spawn_margin"], 30.0),
+        perception: get_perception(&settings["wolf"]["perception"]),
+        den: DenData {
+            size: get_f32(&settings["wolf"]["den"]["size"], 40.0),
+            rest_time: get_f32(&settings["wolf"]["den"]["rest_time"], 3.0),
+        },
+        wariness: get_wariness(&settings["wolf"]["wariness"]),
+    }
+}
+
+pub fn wolf_steering_data(settings: &Value) -> WolfSteeringData {
+    let steering = &settings["wolf"]["steering"];
+
+    WolfSteeringData {
+        wander: get_wander(&steering["wander"]),
+        evade_walls: get_evade_walls(&steering["evade_walls"]),
+        pursue: PursueData {
+            weight: steering["pursue"]["weight"].as_f64().unwrap() as f32,
+            radius: get_f32(&steering["pursue"]["radius"], 100.0),
+        },
+        pack: PackData {
+            size: get_u32(&steering["pack"]["size"], 1).max(1),
+            spread: get_f32(&steering["pack"]["spread"], 40.0),
+            cohesion: get_flocking(&steering["pack"]["cohesion"]),
+            flank: FlankData {
+                weight: get_f32(&steering["pack"]["flank"]["weight"], 1.0),
+                radius: get_f32(&steering["pack"]["flank"]["radius"], 60.0),
+            },
+        },
+        targeting: get_targeting(&steering["targeting"]),
+        territory: TerritoryData {
+            radius: get_f32(&steering["territory"]["radius"], 250.0),
+            leash_distance: get_f32(&steering["territory"]["leash_distance"], 400.0),
+            patrol_weight: get_f32(&steering["territory"]["patrol_weight"], 0.3),
+            return_weight: get_f32(&steering["territory"]["return_weight"], 1.0),
+            rival_weight: get_f32(&steering["territory"]["rival_weight"], 0.5),
+        },
+    }
+}
+
+pub fn deer_data(settings: &Value) -> DeerData {
+    let transform = get_transform(&settings["deer"]["transform"]);
+    let sprite_size = get_f32(&settings["deer"]["sprite_size"], 60.0);
+    let max_number = (settings["deer"]["max_number"].as_u64().unwrap() as u32).max(1);
+
+    DeerData {
+        transform,
+        movement_speed: settings["deer"]["movement_speed"].as_f64().unwrap() as f32,
+        width: sprite_size * transform.scale.x,
+        height: sprite_size * transform.scale.y,
+        max_number,
+        health: get_f32(&settings["deer"]["health"], 1.0),
+        group_number: settings["deer"]["group_number"].as_u64().unwrap() as u32,
+        min_group_size: get_u32(&settings["deer"]["min_group_size"], 3).min(max_number - 1),
+        group_spread: get_f32(&settings["deer"]["group_spread"], 30.0),
+        spawn_margin: get_f32(&settings["deer"]["spawn_margin"], 60.0),
+        perception: get_perception(&settings["deer"]["perception"]),
+    }
+}
+
+pub fn deer_steering_data(settings: &Value) -> DeerSteeringData {
+    let steering = &settings["deer"]["steering"];
+
+    DeerSteeringData {
+        wander: get_wander(&steering["wander"]),
+        evade_walls: get_evade_walls(&steering["evade_walls"]),
+        flee: FleeData {
+            weight: steering["flee"]["weight"].as_f64().unwrap() as f32,
+            max_flee_time: 0.0,
+            radius: get_f32(&steering["flee"]["radius"], 100.0),
+            speed_boost: 0.0,
+        },
+        evade: EvadeData {
+            weight: steering["evade"]["weight"].as_f64().unwrap() as f32,
+            radius: get_f32(&steering["evade"]["radius"], 180.0),
+        },
+        separation: get_flocking(&steering["separation"]),
+        alignment: get_flocking(&steering["alignment"]),
+        cohesion: get_flocking(&steering["cohesion"]),
+        graze: SeekData {
+            weight: get_f32(&steering["graze"]["weight"], 0.0),
+        },
+        herd: HerdData {
+            follow_weight: get_f32(&steering["herd"]["follow_leader"]["weight"], 0.3),
+            follow_distance: get_f32(&steering["herd"]["follow_leader"]["distance"], 50.0),
+            merge_radius: get_f32(&steering["herd"]["merge_radius"], 100.0),
+            split_radius: get_f32(&steering["herd"]["split_radius"], 200.0),
+        },
+        alarm: AlarmData {
+            radius: get_f32(&steering["alarm"]["radius"], 150.0),
+            delay: get_f32(&steering["alarm"]["delay"], 0.3),
+            decay: get_f32(&steering["alarm"]["decay"], 3.0),
+            speed_boost: get_f32(&steering["alarm"]["speed_boost"], 30.0),
+            separation_multiplier: get_f32(&steering["alarm"]["separation_multiplier"], 2.0),
+            flee_weight: get_f32(&steering["alarm"]["flee_weight"], 0.2),
+        },
+    }
+}
+
+pub fn get_transform(transform_data: &Value) -> Transform {
+    let mut transform = Transform::default();
+
+    if transform_data.is_null() {
+        return transform;
+    }
+
+    let translation = &transform_data["translation"];
+    if !translation.is_null() {
+        transform.translation = Vec3::new(
+            translation["x"].as_f64().unwrap() as f32,
+            translation["y"].as_f64().unwrap() as f32,
+            translation["z"].as_f64().unwrap() as f32,
+        )
+    }
+
+    let rotation = &transform_data["rotation"];
+    if !rotation.is_null() {
+        transform.rotation = Quat::from_rotation_ypr(
+            rotation["y"].as_f64().unwrap() as f32,
+            rotation["x"].as_f64().unwrap() as f32,
+            rotation["z"].as_f64().unwrap() as f32,
+        )
+    }
+
+    let scale = &transform_data["scale"];
+    if !scale.is_null() {
+        transform.scale = Vec3::new(
+            scale["x"].as_f64().unwrap() as f32,
+            scale["y"].as_f64().unwrap() as f32,
+            scale["z"].as_f64().unwrap() as f32,
+        )
+    }
+
+    transform
+}
+
+fn get_perception(perception_data: &Value) -> PerceptionData {
+    PerceptionData {
+        field_of_view: get_f32(&perception_data["field_of_view"], 300.0),
+        range: get_f32(&perception_data["range"], 150.0),
+        hearing_radius: get_f32(&perception_data["hearing_radius"], 60.0),
+    }
+}
+
+fn get_wander(wander_data: &Value) -> WanderData {
+    WanderData {
+        weight: wander_data["weight"].as_f64().unwrap() as f32,
+        displace_range: wander_data["displace_range"].as_f64().unwrap() as f32,
+        radius: wander_data["radius"].as_f64().unwrap() as f32,
+        max_force: wander_data["max_force"].as_f64().unwrap() as f32,
+        distance: wander_data["distance"].as_f64().unwrap() as f32,
+    }
+}
+
+fn get_evade_walls(evade_walls_data: &Value) -> EvadeWallsData {
+    EvadeWallsData {
+        weight: evade_walls_data["weight"].as_f64().unwrap() as f32,
+        look_ahead: get_f32(&evade_walls_data["look_ahead"], 40.0),
+    }
+}
+
+fn get_flocking(flocking_data: &Value) -> FlockingData {
+    FlockingData {
+        perception_radius: flocking_data["perception_radius"].as_f64().unwrap() as f32,
+        max_force: flocking_data["max_force"].as_f64().unwrap() as f32,
+    }
+}
+
+fn get_wariness(wariness_data: &Value) -> WarinessData {
+    WarinessData {
+        gunshot_fear: get_f32(&wariness_data["gunshot_fear"], 0.0),
+        hit_fear: get_f32(&wariness_data["hit_fear"], 0.0),
+        distance_fear: get_f32(&wariness_data["distance_fear"], 0.0),
+        fear_radius: get_f32(&wariness_data["fear_radius"], 0.0),
+        decay: get_f32(&wariness_data["decay"], 0.1),
+        retreat_threshold: get_f32(&wariness_data["retreat_threshold"], 1.0),
+        retreat_weight: get_f32(&wariness_data["retreat_weight"], 0.0),
+        starving_threshold: get_f32(&wariness_data["starving_threshold"], 1.0),
+        starving_aggression: get_f32(&wariness_data["starving_aggression"], 0.0),
+    }
+}
+
+fn get_targeting(targeting_data: &Value) -> TargetingData {
+    TargetingData {
+        distance_weight: get_f32(&targeting_data["distance_weight"], 1.0),
+        speed_weight: get_f32(&targeting_data["speed_weight"], 0.5),
+        health_weight: get_f32(&targeting_data["health_weight"], 0.3),
+        player_weight: get_f32(&targeting_data["player_weight"], 0.2),
+        hysteresis: get_f32(&targeting_data["hysteresis"], 0.2),
+        max_chase_time: get_f32(&targeting_data["max_chase_time"], 8.0),
+    }
+}
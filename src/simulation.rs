@@ -0,0 +1,397 @@
+use bevy::{
+    ecs::schedule::ShouldRun,
+    input::InputSystem,
+    prelude::*,
+    render::RenderStage,
+    window::{WindowFocused, WindowResized},
+};
+
+use rand::{Error, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    controls::{Action, ActionState},
+    TIME_STEP,
+};
+
+pub(crate) const SIMULATION: &str = "simulation";
+
+const MIN_TIME_SCALE: f32 = 0.1;
+const MAX_TIME_SCALE: f32 = 10.0;
+
+pub(crate) struct SimulationState {
+    pub focused: bool,
+    pub minimized: bool,
+}
+
+impl SimulationState {
+    pub fn is_running(&self) -> bool {
+        self.focused && !self.minimized
+    }
+}
+
+impl Default for SimulationState {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            minimized: false,
+        }
+    }
+}
+
+pub(crate) struct SimulationClock {
+    pub elapsed: f32,
+    pub scale: f32,
+    pub paused: bool,
+    pub max_steps_per_frame: u32,
+    accumulator: f32,
+    steps: u32,
+    looping: bool,
+    step_requested: bool,
+}
+
+impl SimulationClock {
+    pub fn new(scale: f32, max_steps_per_frame: u32) -> Self {
+        Self {
+            elapsed: 0.0,
+            scale: scale.max(MIN_TIME_SCALE).min(MAX_TIME_SCALE),
+            paused: false,
+            max_steps_per_frame: max_steps_per_frame.max(1),
+            accumulator: 0.0,
+            steps: 0,
+            looping: false,
+            step_requested: false,
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(MIN_TIME_SCALE).min(MAX_TIME_SCALE);
+    }
+
+    pub fn step(&mut self) {
+        self.step_requested = true;
+    }
+
+    fn advance(&mut self, delta: f32) -> ShouldRun {
+        if !self.looping {
+            self.steps = 0;
+
+            if self.paused {
+                self.accumulator = 0.0;
+            } else {
+                self.accumulator += delta * self.scale;
+            }
+
+            if self.step_requested {
+                self.step_requested = false;
+                self.accumulator += TIME_STEP;
+            }
+        }
+
+        if self.accumulator >= TIME_STEP && self.steps < self.max_steps_per_frame {
+            self.accumulator -= TIME_STEP;
+            self.elapsed += TIME_STEP;
+            self.steps += 1;
+            self.looping = true;
+            ShouldRun::YesAndCheckAgain
+        } else {
+            if self.steps >= self.max_steps_per_frame {
+                self.accumulator = self.accumulator.min(TIME_STEP);
+            }
+
+            self.looping = false;
+            ShouldRun::No
+        }
+    }
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::new(1.0, 10)
+    }
+}
+
+pub(crate) struct SimRng {
+    seed: u64,
+    rng: ChaCha12Rng,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn from_state(seed: u64, word_pos: u128) -> Self {
+        let mut rng = Self::new(seed);
+        rng.rng.set_word_pos(word_pos);
+        rng
+    }
+
+    pub fn word_pos(&self) -> u128 {
+        self.rng.get_word_pos()
+    }
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(SimulationState::default())
+            .init_resource::<SimulationClock>()
+            .add_stage_after(
+                CoreStage::Update,
+                SIMULATION,
+                SystemStage::parallel().with_run_criteria(simulation_running.system()),
+            )
+            .add_system_to_stage(CoreStage::PreUpdate, window_state.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                clock_controls.system().after(InputSystem).after("actions"),
+            );
+
+        if app
+            .app
+            .schedule
+            .get_stage::<SystemStage>(&RenderStage::Render)
+            .is_some()
+        {
+            app.stage(RenderStage::Render, |stage: &mut SystemStage| {
+                stage.set_run_criteria(window_visible.system())
+            });
+        }
+    }
+}
+
+fn window_state(
+    mut state: ResMut<SimulationState>,
+    mut focused_events: EventReader<WindowFocused>,
+    mut resized_events: EventReader<WindowResized>,
+) {
+    for event in focused_events.iter() {
+        state.focused = event.focused;
+    }
+
+    for event in resized_events.iter() {
+        state.minimized = event.width <= 0.0 || event.height <= 0.0;
+    }
+}
+
+fn clock_controls(actions: Res<ActionState>, mut clock: ResMut<SimulationClock>) {
+    if actions.just_pressed(Action::Pause) {
+        clock.paused = !clock.paused;
+    }
+    if actions.just_pressed(Action::SlowDown) {
+        let scale = clock.scale / 2.0;
+        clock.set_scale(scale);
+    }
+    if actions.just_pressed(Action::SpeedUp) {
+        let scale = clock.scale * 2.0;
+        clock.set_scale(scale);
+    }
+    if actions.just_pressed(Action::Step) && clock.paused {
+        clock.step();
+    }
+}
+
+fn simulation_running(
+    state: Res<SimulationState>,
+    mut clock: ResMut<SimulationClock>,
+    time: Res<Time>,
+) -> ShouldRun {
+    if !state.is_running() {
+        return ShouldRun::No;
+    }
+
+    clock.advance(time.delta_seconds())
+}
+
+fn window_visible(state: Res<SimulationState>) -> ShouldRun {
+    if state.minimized {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::window::WindowId;
+
+    struct Ticks(u32);
+
+    fn tick(mut ticks: ResMut<Ticks>) {
+        ticks.0 += 1;
+    }
+
+    fn app() -> App {
+        let mut builder = App::build();
+        builder
+            .insert_resource(Time::default())
+            .init_resource::<ActionState>()
+            .add_event::<WindowFocused>()
+            .add_event::<WindowResized>()
+            .add_plugin(SimulationPlugin)
+            .insert_resource(Ticks(0))
+            .add_system_to_stage(SIMULATION, tick.system());
+
+        builder.app
+    }
+
+    fn ticks(app: &App) -> u32 {
+        app.world.get_resource::<Ticks>().unwrap().0
+    }
+
+    fn focus(app: &mut App, focused: bool) {
+        app.world
+            .get_resource_mut::<Events<WindowFocused>>()
+            .unwrap()
+            .send(WindowFocused {
+                id: WindowId::primary(),
+                focused,
+            });
+    }
+
+    fn step(app: &mut App) {
+        app.world
+            .get_resource_mut::<SimulationClock>()
+            .unwrap()
+            .step();
+        app.update();
+    }
+
+    fn resize(app: &mut App, width: f32, height: f32) {
+        app.world
+            .get_resource_mut::<Events<WindowResized>>()
+            .unwrap()
+            .send(WindowResized {
+                id: WindowId::primary(),
+                width,
+                height,
+            });
+    }
+
+    #[test]
+    fn runs_while_focused() {
+        let mut app = app();
+
+        step(&mut app);
+        step(&mut app);
+
+        assert_eq!(ticks(&app), 2);
+    }
+
+    #[test]
+    fn pauses_on_focus_loss_and_resumes_on_focus() {
+        let mut app = app();
+        step(&mut app);
+
+        focus(&mut app, false);
+        step(&mut app);
+        step(&mut app);
+        assert_eq!(ticks(&app), 1);
+
+        focus(&mut app, true);
+        step(&mut app);
+        assert_eq!(ticks(&app), 2);
+    }
+
+    #[test]
+    fn pauses_while_minimized() {
+        let mut app = app();
+        step(&mut app);
+
+        resize(&mut app, 0.0, 0.0);
+        step(&mut app);
+        assert_eq!(ticks(&app), 1);
+        assert!(
+            app.world
+                .get_resource::<SimulationState>()
+                .unwrap()
+                .minimized
+        );
+
+        resize(&mut app, 1280.0, 720.0);
+        step(&mut app);
+        assert_eq!(ticks(&app), 2);
+    }
+
+    #[test]
+    fn paused_clock_only_advances_on_step() {
+        let mut app = app();
+        app.world
+            .get_resource_mut::<SimulationClock>()
+            .unwrap()
+            .paused = true;
+
+        app.update();
+        assert_eq!(ticks(&app), 0);
+
+        step(&mut app);
+        assert_eq!(ticks(&app), 1);
+        assert_eq!(
+            app.world.get_resource::<SimulationClock>().unwrap().elapsed,
+            TIME_STEP
+        );
+    }
+
+    #[test]
+    fn rng_resumes_from_saved_state() {
+        let mut rng = SimRng::new(7);
+        rng.next_u64();
+        rng.next_u32();
+
+        let mut resumed = SimRng::from_state(rng.seed(), rng.word_pos());
+
+        for _ in 0..10 {
+            assert_eq!(rng.next_u64(), resumed.next_u64());
+        }
+    }
+
+    #[test]
+    fn time_scale_is_clamped() {
+        let mut clock = SimulationClock::new(100.0, 10);
+        assert_eq!(clock.scale, MAX_TIME_SCALE);
+
+        clock.set_scale(0.01);
+        assert_eq!(clock.scale, MIN_TIME_SCALE);
+    }
+
+    #[test]
+    fn steps_per_frame_are_capped() {
+        let mut clock = SimulationClock::new(10.0, 5);
+        let mut steps = 0;
+
+        while let ShouldRun::YesAndCheckAgain = clock.advance(1.0) {
+            steps += 1;
+        }
+
+        assert_eq!(steps, 5);
+        assert!(clock.accumulator <= TIME_STEP);
+    }
+}
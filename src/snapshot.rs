@@ -0,0 +1,339 @@
+use bevy::prelude::*;
+use serde_json::{from_str, json, Value};
+use std::{collections::HashMap, fs, io};
+
+use crate::{
+    components::Health,
+    deer::{self, Deer},
+    hare::{self, Hare},
+    map::MapLayout,
+    perception::Perception,
+    player::{self, Bullet, Player},
+    settings::{get_f32, get_u32, write_json},
+    simulation::{SimRng, SimulationClock},
+    stats::{Score, SimulationStats},
+    steering::Physics,
+    targeting::Target,
+    wariness::Wariness,
+    wolf::{self, Den, Wolf},
+};
+
+const SNAPSHOT_VERSION: u32 = 2;
+const ENTITY_LISTS: [(&str, &str); 6] = [
+    ("player", "entities"),
+    ("player", "bullets"),
+    ("hares", "entities"),
+    ("wolves", "entities"),
+    ("wolves", "dens"),
+    ("deer", "entities"),
+];
+
+pub struct SnapshotData {
+    pub path: String,
+    pub load_on_start: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct EntityMap {
+    entities: HashMap<u64, Entity>,
+}
+
+impl EntityMap {
+    pub fn get(&self, id: &Value) -> Option<Entity> {
+        id.as_u64().and_then(|id| self.entities.get(&id).copied())
+    }
+
+    pub fn records<'a>(
+        &'a self,
+        list: &'a Value,
+    ) -> impl Iterator<Item = (Entity, &'a Value)> + 'a {
+        list.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(move |record| self.get(&record["id"]).map(|entity| (entity, record)))
+    }
+}
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            snapshot_controls.exclusive_system().at_end(),
+        );
+    }
+}
+
+fn snapshot_controls(world: &mut World) {
+    let (save, load) = match world.get_resource::<Input<KeyCode>>() {
+        Some(keyboard_input) => (
+            keyboard_input.just_pressed(KeyCode::F5),
+            keyboard_input.just_pressed(KeyCode::F9),
+        ),
+        None => (false, false),
+    };
+
+    let mut snapshot_data = world.get_resource_mut::<SnapshotData>().unwrap();
+    let load = load || snapshot_data.load_on_start;
+    snapshot_data.load_on_start = false;
+    let path = snapshot_data.path.clone();
+
+    if save {
+        match write_snapshot(world, &path) {
+            Ok(()) => info!("Saved snapshot to {}", path),
+            Err(error) => warn!("Failed to save snapshot to {}: {}", path, error),
+        }
+    }
+
+    if load {
+        match read_snapshot(world, &path) {
+            Ok(()) => info!("Loaded snapshot from {}", path),
+            Err(error) => warn!("Failed to load snapshot from {}: {}", path, error),
+        }
+    }
+}
+
+fn write_snapshot(world: &mut World, path: &str) -> io::Result<()> {
+    let rng = world.get_resource::<SimRng>().unwrap();
+    let rng = json!({
+        "seed": rng.seed(),
+        "word_pos": rng.word_pos().to_string(),
+    });
+    let clock = world.get_resource::<SimulationClock>().unwrap();
+    let clock = json!({
+        "elapsed": clock.elapsed,
+        "scale": clock.scale,
+        "paused": clock.paused,
+    });
+    let score = world.get_resource::<Score>().unwrap().value;
+    let stats = world.get_resource::<SimulationStats>().unwrap();
+    let stats = json!({
+        "shot": stats.shot,
+        "eaten": stats.eaten,
+        "starved": stats.starved,
+    });
+    let map = world.get_resource::<MapLayout>().unwrap().to_value();
+
+    let snapshot = json!({
+        "version": SNAPSHOT_VERSION,
+        "rng": rng,
+        "clock": clock,
+        "score": score,
+        "stats": stats,
+        "map": map,
+        "player": player::save_snapshot(world),
+        "hares": hare::save_snapshot(world),
+        "wolves": wolf::save_snapshot(world),
+        "deer": deer::save_snapshot(world),
+    });
+
+    write_json(path, &snapshot)
+}
+
+fn read_snapshot(world: &mut World, path: &str) -> io::Result<()> {
+    let snapshot: Value = from_str(fs::read_to_string(path)?.as_str())?;
+
+    if get_u32(&snapshot["version"], 0) != SNAPSHOT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported snapshot version",
+        ));
+    }
+
+    let despawned: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(
+            With<Player>,
+            With<Bullet>,
+            With<Hare>,
+            With<Wolf>,
+            With<Den>,
+            With<Deer>,
+        )>>()
+        .iter(world)
+        .collect();
+
+    for entity in despawned {
+        world.despawn(entity);
+    }
+
+    let mut entities = EntityMap::default();
+
+    for (section, list) in ENTITY_LISTS.iter() {
+        for record in snapshot[section][list].as_array().into_iter().flatten() {
+            if let Some(id) = record["id"].as_u64() {
+                entities.entities.insert(id, world.spawn().id());
+            }
+        }
+    }
+
+    world.insert_resource(SimRng::from_state(
+        snapshot["rng"]["seed"].as_u64().unwrap_or(0),
+        snapshot["rng"]["word_pos"]
+            .as_str()
+            .and_then(|word_pos| word_pos.parse().ok())
+            .unwrap_or(0),
+    ));
+
+    let mut clock = world.get_resource_mut::<SimulationClock>().unwrap();
+    clock.elapsed = get_f32(&snapshot["clock"]["elapsed"], 0.0);
+    clock.set_scale(get_f32(&snapshot["clock"]["scale"], 1.0));
+    clock.paused = snapshot["clock"]["paused"].as_bool().unwrap_or(false);
+
+    world.get_resource_mut::<Score>().unwrap().value = get_u32(&snapshot["score"], 0);
+
+    let mut stats = world.get_resource_mut::<SimulationStats>().unwrap();
+    stats.shot = get_u32(&snapshot["stats"]["shot"], 0);
+    stats.eaten = get_u32(&snapshot["stats"]["eaten"], 0);
+    stats.starved = get_u32(&snapshot["stats"]["starved"], 0);
+
+    world.insert_resource(MapLayout::from_value(&snapshot["map"]));
+
+    player::load_snapshot(world, &snapshot["player"], &entities);
+    hare::load_snapshot(world, &snapshot["hares"], &entities);
+    wolf::load_snapshot(world, &snapshot["wolves"], &entities);
+    deer::load_snapshot(world, &snapshot["deer"], &entities);
+
+    Ok(())
+}
+
+pub(crate) fn entity_value(entity: Option<Entity>) -> Value {
+    match entity {
+        Some(entity) => json!(entity.id()),
+        None => Value::Null,
+    }
+}
+
+pub(crate) fn vec3_value(vec: Vec3) -> Value {
+    json!([vec.x, vec.y, vec.z])
+}
+
+pub(crate) fn get_vec3(value: &Value) -> Vec3 {
+    Vec3::new(
+        get_f32(&value[0], 0.0),
+        get_f32(&value[1], 0.0),
+        get_f32(&value[2], 0.0),
+    )
+}
+
+pub(crate) fn transform_value(transform: &Transform) -> Value {
+    let rotation = transform.rotation;
+
+    json!({
+        "translation": vec3_value(transform.translation),
+        "rotation": [rotation.x, rotation.y, rotation.z, rotation.w],
+        "scale": vec3_value(transform.scale),
+    })
+}
+
+pub(crate) fn get_transform(value: &Value) -> Transform {
+    let rotation = &value["rotation"];
+
+    Transform {
+        translation: get_vec3(&value["translation"]),
+        rotation: Quat::from_xyzw(
+            get_f32(&rotation[0], 0.0),
+            get_f32(&rotation[1], 0.0),
+            get_f32(&rotation[2], 0.0),
+            get_f32(&rotation[3], 1.0),
+        ),
+        scale: get_vec3(&value["scale"]),
+    }
+}
+
+pub(crate) fn physics_value(physics: &Physics) -> Value {
+    json!({
+        "velocity": vec3_value(physics.velocity),
+        "acceleration": vec3_value(physics.acceleration),
+        "wander_theta": physics.wander_theta,
+    })
+}
+
+pub(crate) fn get_physics(value: &Value) -> Physics {
+    Physics {
+        velocity: get_vec3(&value["velocity"]),
+        acceleration: get_vec3(&value["acceleration"]),
+        wander_theta: get_f32(&value["wander_theta"], 0.0),
+    }
+}
+
+pub(crate) fn health_value(health: &Health) -> Value {
+    json!({ "value": health.value, "max": health.max })
+}
+
+pub(crate) fn get_health(value: &Value) -> Health {
+    Health {
+        value: get_f32(&value["value"], 1.0),
+        max: get_f32(&value["max"], 1.0),
+    }
+}
+
+pub(crate) fn perception_value(perception: &Perception) -> Value {
+    json!({
+        "field_of_view": perception.field_of_view,
+        "range": perception.range,
+        "hearing_radius": perception.hearing_radius,
+        "perceived": perception
+            .perceived
+            .iter()
+            .map(|entity| entity_value(Some(*entity)))
+            .collect::<Vec<_>>(),
+        "gunshots": perception
+            .gunshots
+            .iter()
+            .map(|gunshot| vec3_value(*gunshot))
+            .collect::<Vec<_>>(),
+    })
+}
+
+pub(crate) fn get_perception(value: &Value, entities: &EntityMap) -> Perception {
+    Perception {
+        field_of_view: get_f32(&value["field_of_view"], 0.0),
+        range: get_f32(&value["range"], 0.0),
+        hearing_radius: get_f32(&value["hearing_radius"], 0.0),
+        perceived: value["perceived"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| entities.get(id))
+            .collect(),
+        gunshots: value["gunshots"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(get_vec3)
+            .collect(),
+    }
+}
+
+pub(crate) fn target_value(target: &Target) -> Value {
+    json!({
+        "entity": entity_value(target.entity),
+        "score": target.score,
+        "chase_started": target.chase_started,
+        "abandoned": entity_value(target.abandoned),
+    })
+}
+
+pub(crate) fn get_target(value: &Value, entities: &EntityMap) -> Target {
+    Target {
+        entity: entities.get(&value["entity"]),
+        score: get_f32(&value["score"], 0.0),
+        chase_started: get_f32(&value["chase_started"], 0.0),
+        abandoned: entities.get(&value["abandoned"]),
+    }
+}
+
+pub(crate) fn wariness_value(wariness: &Wariness) -> Value {
+    json!({
+        "fear": wariness.fear,
+        "aggression": wariness.aggression,
+        "alerted": wariness.alerted,
+    })
+}
+
+pub(crate) fn load_wariness(value: &Value, wariness: &mut Wariness) {
+    wariness.fear = get_f32(&value["fear"], 0.0);
+    wariness.aggression = get_f32(&value["aggression"], 0.0);
+    wariness.alerted = value["alerted"].as_bool().unwrap_or(false);
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::{
+    deer::Deer,
+    hare::Hare,
+    player::Player,
+    simulation::{SimulationClock, SIMULATION},
+    utils::dist,
+    wolf::{Wolf, WolfBehavior},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Species {
+    Player,
+    Hare,
+    Wolf,
+    Deer,
+}
+
+impl Species {
+    pub const ALL: [Species; 4] = [Species::Player, Species::Hare, Species::Wolf, Species::Deer];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Species::Player => "player",
+            Species::Hare => "hare",
+            Species::Wolf => "wolf",
+            Species::Deer => "deer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|species| species.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DeathCause {
+    Shot,
+    Eaten,
+    Starved,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 3] = [DeathCause::Shot, DeathCause::Eaten, DeathCause::Starved];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            DeathCause::Shot => "shot",
+            DeathCause::Eaten => "eaten",
+            DeathCause::Starved => "starved",
+        }
+    }
+}
+
+pub struct DeathEvent {
+    pub species: Species,
+    pub cause: DeathCause,
+}
+
+pub(crate) struct BirthEvent {
+    pub species: Species,
+}
+
+pub struct ScoreData {
+    pub hare: u32,
+    pub wolf: u32,
+    pub deer: u32,
+}
+
+impl ScoreData {
+    fn points(&self, species: Species) -> u32 {
+        match species {
+            Species::Player => 0,
+            Species::Hare => self.hare,
+            Species::Wolf => self.wolf,
+            Species::Deer => self.deer,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Score {
+    pub value: u32,
+}
+
+#[derive(Default)]
+pub struct SimulationStats {
+    pub hares: u32,
+    pub wolves: u32,
+    pub deer: u32,
+    pub nearest_wolf_hunger: Option<f32>,
+    pub elapsed: f32,
+    pub shot: u32,
+    pub eaten: u32,
+    pub starved: u32,
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<DeathEvent>()
+            .add_event::<BirthEvent>()
+            .init_resource::<Score>()
+            .init_resource::<SimulationStats>()
+            .add_system_to_stage(
+                SIMULATION,
+                score_update
+                    .system()
+                    .label("score_update")
+                    .after("player_die")
+                    .after("hare_die")
+                    .after("wolf_starve")
+                    .after("wolf_die")
+                    .after("deer_die"),
+            )
+            .add_system_to_stage(SIMULATION, stats_update.system().label("stats_update"));
+    }
+}
+
+fn score_update(
+    mut deaths: EventReader<DeathEvent>,
+    mut score: ResMut<Score>,
+    mut stats: ResMut<SimulationStats>,
+    score_data: Res<ScoreData>,
+) {
+    for death in deaths.iter() {
+        match death.cause {
+            DeathCause::Shot => {
+                score.value += score_data.points(death.species);
+                stats.shot += 1;
+            }
+            DeathCause::Eaten => stats.eaten += 1,
+            DeathCause::Starved => stats.starved += 1,
+        }
+    }
+}
+
+fn stats_update(
+    mut stats: ResMut<SimulationStats>,
+    hare_query: Query<&Hare>,
+    deer_query: Query<&Deer>,
+    wolf_query: Query<(&Transform, &WolfBehavior), With<Wolf>>,
+    player_query: Query<&Transform, With<Player>>,
+    clock: Res<SimulationClock>,
+) {
+    stats.hares = hare_query.iter().count() as u32;
+    stats.deer = deer_query.iter().count() as u32;
+    stats.wolves = wolf_query.iter().count() as u32;
+    stats.elapsed = clock.elapsed;
+
+    stats.nearest_wolf_hunger = player_query.single().ok().and_then(|player_transform| {
+        wolf_query
+            .iter()
+            .min_by(|(a, _), (b, _)| {
+                dist(player_transform.translation, a.translation)
+                    .total_cmp(&dist(player_transform.translation, b.translation))
+            })
+            .map(|(_, behavior)| behavior.hunger(clock.elapsed))
+    });
+}
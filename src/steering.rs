@@ -7,6 +7,63 @@ pub(crate) struct Physics {
     pub wander_theta: f32,
 }
 
+pub(crate) const STEERING_KINDS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SteeringKind {
+    Wander,
+    Flee,
+    Seek,
+    Pursue,
+    Separation,
+    Alignment,
+    Cohesion,
+    Walls,
+}
+
+#[derive(Default)]
+pub(crate) struct SteeringDebug {
+    forces: [Vec3; STEERING_KINDS],
+    pub applied: [Vec3; STEERING_KINDS],
+    pub wander: Option<(Vec3, f32, Vec3)>,
+    pub wall_ray: Option<(Vec3, Vec3)>,
+}
+
+impl SteeringDebug {
+    pub fn record(&mut self, kind: SteeringKind, force: Vec3) -> Vec3 {
+        self.forces[kind as usize] += force;
+        force
+    }
+
+    pub fn record_wander(
+        &mut self,
+        position: Vec3,
+        velocity: Vec3,
+        wander_radius: f32,
+        distance: f32,
+        wander_theta: f32,
+    ) {
+        let (center, target) =
+            wander_circle(position, velocity, wander_radius, distance, wander_theta);
+        self.wander = Some((center, wander_radius, target));
+    }
+
+    pub fn record_wall_ray(&mut self, position: Vec3, velocity: Vec3, look_ahead: f32) {
+        self.wall_ray = Some((position, position + set_mag(velocity, look_ahead)));
+    }
+
+    pub fn total(&self) -> Vec3 {
+        self.applied
+            .iter()
+            .fold(Vec3::ZERO, |total, force| total + *force)
+    }
+
+    pub fn flush(&mut self) {
+        self.applied = self.forces;
+        self.forces = [Vec3::ZERO; STEERING_KINDS];
+    }
+}
+
 pub struct WanderData {
     pub weight: f32,
     pub displace_range: f32,
@@ -18,18 +75,32 @@ pub struct WanderData {
 pub struct FleeData {
     pub weight: f32,
     pub max_flee_time: f32,
+    pub radius: f32,
+    pub speed_boost: f32,
+}
+
+pub struct SeekData {
+    pub weight: f32,
 }
 
 pub struct PursueData {
     pub weight: f32,
+    pub radius: f32,
 }
 
 pub struct EvadeData {
     pub weight: f32,
+    pub radius: f32,
 }
 
 pub struct EvadeWallsData {
     pub weight: f32,
+    pub look_ahead: f32,
+}
+
+pub struct FlankData {
+    pub weight: f32,
+    pub radius: f32,
 }
 
 pub struct FlockingData {
@@ -63,13 +134,8 @@ pub fn wander(
     wander_theta: f32,
     max_force: f32,
 ) -> Vec3 {
-    let mut wander_point = set_mag(velocity, distance);
-    wander_point += position;
-
-    let theta = wander_theta + velocity.y.atan2(velocity.x);
-    let x = wander_radius * theta.cos();
-    let y = wander_radius * theta.sin();
-    wander_point += Vec3::new(x, y, 0.0);
+    let (_, wander_point) =
+        wander_circle(position, velocity, wander_radius, distance, wander_theta);
 
     let mut steer = wander_point - position;
     steer = set_mag(steer, max_force);
@@ -77,6 +143,22 @@ pub fn wander(
     steer
 }
 
+pub fn wander_circle(
+    position: Vec3,
+    velocity: Vec3,
+    wander_radius: f32,
+    distance: f32,
+    wander_theta: f32,
+) -> (Vec3, Vec3) {
+    let center = position + set_mag(velocity, distance);
+
+    let theta = wander_theta + velocity.y.atan2(velocity.x);
+    let x = wander_radius * theta.cos();
+    let y = wander_radius * theta.sin();
+
+    (center, center + Vec3::new(x, y, 0.0))
+}
+
 pub fn pursue(
     position: Vec3,
     velocity: Vec3,
@@ -102,3 +184,169 @@ pub fn evade(
     let future_position = target_position + target_velocity * t;
     flee(position, velocity, future_position, max_speed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{cases, random_vec};
+    use rand::Rng;
+    use std::f32::consts::PI;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn seek_is_limited_to_max_speed() {
+        cases(|rng| {
+            let max_speed = rng.gen_range(0.1..10.0);
+            let steer = seek(
+                random_vec(rng, 1000.0),
+                random_vec(rng, 20.0),
+                random_vec(rng, 1000.0),
+                max_speed,
+            );
+
+            assert!(steer.length() <= max_speed + EPSILON);
+        });
+    }
+
+    #[test]
+    fn seek_from_rest_heads_to_target_at_max_speed() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let target = random_vec(rng, 1000.0);
+            let max_speed = rng.gen_range(0.1..10.0);
+            let steer = seek(position, Vec3::ZERO, target, max_speed);
+
+            assert!((steer.length() - max_speed).abs() < EPSILON);
+            assert!(steer.dot(target - position) > 0.0);
+        });
+    }
+
+    #[test]
+    fn flee_mirrors_seek() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let velocity = random_vec(rng, 20.0);
+            let target = random_vec(rng, 1000.0);
+            let max_speed = rng.gen_range(0.1..10.0);
+
+            assert_close(
+                flee(position, velocity, target, max_speed),
+                -seek(position, -velocity, target, max_speed),
+            );
+        });
+    }
+
+    #[test]
+    fn flee_from_rest_heads_away_from_target() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let target = random_vec(rng, 1000.0);
+            let max_speed = rng.gen_range(0.1..10.0);
+            let steer = flee(position, Vec3::ZERO, target, max_speed);
+
+            assert!(steer.length() <= max_speed + EPSILON);
+            assert!(steer.dot(target - position) < 0.0);
+        });
+    }
+
+    #[test]
+    fn pursue_and_evade_of_still_target_match_seek_and_flee() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let velocity = random_vec(rng, 20.0);
+            let target = random_vec(rng, 1000.0);
+            let max_speed = rng.gen_range(0.1..10.0);
+
+            assert_close(
+                pursue(position, velocity, target, Vec3::ZERO, max_speed),
+                seek(position, velocity, target, max_speed),
+            );
+            assert_close(
+                evade(position, velocity, target, Vec3::ZERO, max_speed),
+                flee(position, velocity, target, max_speed),
+            );
+        });
+    }
+
+    #[test]
+    fn pursue_leads_a_moving_target() {
+        let steer = pursue(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 5.0, 0.0),
+            5.0,
+        );
+
+        assert!(steer.x > 0.0);
+        assert!(steer.y > 0.0);
+    }
+
+    #[test]
+    fn evade_and_pursue_are_limited_to_max_speed() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let velocity = random_vec(rng, 20.0);
+            let target = random_vec(rng, 1000.0);
+            let target_velocity = random_vec(rng, 20.0);
+            let max_speed = rng.gen_range(0.1..10.0);
+
+            let pursue = pursue(position, velocity, target, target_velocity, max_speed);
+            let evade = evade(position, velocity, target, target_velocity, max_speed);
+
+            assert!(pursue.length() <= max_speed + EPSILON);
+            assert!(evade.length() <= max_speed + EPSILON);
+        });
+    }
+
+    #[test]
+    fn wander_has_max_force_magnitude() {
+        cases(|rng| {
+            let velocity = random_vec(rng, 20.0);
+            let radius = rng.gen_range(1.0..50.0);
+            let distance = rng.gen_range(radius + 1.0..200.0);
+            let max_force = rng.gen_range(0.1..10.0);
+            let steer = wander(
+                random_vec(rng, 1000.0),
+                velocity,
+                radius,
+                distance,
+                rng.gen_range(-PI..PI),
+                max_force,
+            );
+
+            assert!((steer.length() - max_force).abs() < EPSILON);
+            assert!(steer.dot(velocity) > 0.0);
+        });
+    }
+
+    #[test]
+    fn wander_with_zero_theta_steers_straight_ahead() {
+        cases(|rng| {
+            let velocity = random_vec(rng, 20.0);
+            let steer = wander(random_vec(rng, 1000.0), velocity, 10.0, 50.0, 0.0, 1.0);
+
+            assert_close(steer, velocity.normalize());
+        });
+    }
+
+    #[test]
+    fn wander_circle_target_lies_on_circle() {
+        cases(|rng| {
+            let position = random_vec(rng, 1000.0);
+            let velocity = random_vec(rng, 20.0);
+            let radius = rng.gen_range(1.0..50.0);
+            let distance = rng.gen_range(1.0..200.0);
+            let (center, target) =
+                wander_circle(position, velocity, radius, distance, rng.gen_range(-PI..PI));
+
+            assert!(((center - position).length() - distance).abs() < EPSILON);
+            assert!(((target - center).length() - radius).abs() < EPSILON);
+        });
+    }
+}
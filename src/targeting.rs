@@ -0,0 +1,50 @@
+use bevy::prelude::Entity;
+
+pub struct TargetingData {
+    pub distance_weight: f32,
+    pub speed_weight: f32,
+    pub health_weight: f32,
+    pub player_weight: f32,
+    pub hysteresis: f32,
+    pub max_chase_time: f32,
+}
+
+impl TargetingData {
+    pub fn score(
+        &self,
+        distance: f32,
+        range: f32,
+        relative_speed: f32,
+        health_fraction: f32,
+        is_player: bool,
+    ) -> f32 {
+        let closeness = (1.0 - distance / range).max(0.0);
+        let slowness = (1.0 - relative_speed).max(0.0);
+        let weakness = (1.0 - health_fraction).max(0.0);
+        let player = if is_player { 1.0 } else { 0.0 };
+
+        closeness * self.distance_weight
+            + slowness * self.speed_weight
+            + weakness * self.health_weight
+            + player * self.player_weight
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Target {
+    pub entity: Option<Entity>,
+    pub score: f32,
+    pub chase_started: f32,
+    pub abandoned: Option<Entity>,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self {
+            entity: None,
+            score: 0.0,
+            chase_started: 0.0,
+            abandoned: None,
+        }
+    }
+}
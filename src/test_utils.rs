@@ -0,0 +1,20 @@
+use bevy::math::{Vec2, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const CASES: usize = 1000;
+
+pub fn random_vec2(rng: &mut StdRng, range: f32) -> Vec2 {
+    Vec2::new(rng.gen_range(-range..range), rng.gen_range(-range..range))
+}
+
+pub fn random_vec(rng: &mut StdRng, range: f32) -> Vec3 {
+    random_vec2(rng, range).extend(0.0)
+}
+
+pub fn cases(mut check: impl FnMut(&mut StdRng)) {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..CASES {
+        check(&mut rng);
+    }
+}
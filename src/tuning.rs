@@ -0,0 +1,284 @@
+use bevy::{input::InputSystem, prelude::*};
+use serde_json::Value;
+
+use crate::{
+    components::MovementSpeed,
+    deer::Deer,
+    hare::Hare,
+    player::Player,
+    settings::{self, Settings},
+    wolf::Wolf,
+    FONT_PATH,
+};
+
+const SPECIES: [&str; 4] = ["player", "hare", "wolf", "deer"];
+const VISIBLE_PARAMS: usize = 16;
+const FINE_STEP: f64 = 0.1;
+const PRECISION: f64 = 10000.0;
+
+struct TuningParam {
+    pointer: String,
+    step: f64,
+}
+
+impl TuningParam {
+    fn new(pointer: String, value: f64) -> Self {
+        let step = if value == 0.0 { 0.1 } else { value.abs() * 0.1 };
+
+        Self { pointer, step }
+    }
+
+    fn name(&self) -> String {
+        self.pointer.trim_start_matches('/').replace('/', ".")
+    }
+}
+
+struct TuningPanel {
+    open: bool,
+    params: Vec<TuningParam>,
+    selected: usize,
+    status: String,
+}
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(tuning_spawn.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                tuning_controls
+                    .system()
+                    .label("tuning_controls")
+                    .after(InputSystem)
+                    .after("bindings_menu_controls")
+                    .before("actions"),
+            )
+            .add_system(tuning_update.system().label("tuning"));
+    }
+}
+
+struct TuningText;
+
+fn tuning_spawn(mut commands: Commands, asset_server: Res<AssetServer>, settings: Res<Settings>) {
+    let mut params = Vec::new();
+
+    for species in SPECIES.iter() {
+        let speed_pointer = format!("/{}/movement_speed", species);
+
+        if let Some(speed) = settings
+            .value
+            .pointer(&speed_pointer)
+            .and_then(Value::as_f64)
+        {
+            params.push(TuningParam::new(speed_pointer, speed));
+        }
+
+        collect_params(
+            &settings.value[species]["steering"],
+            format!("/{}/steering", species),
+            &mut params,
+        );
+    }
+
+    commands.insert_resource(TuningPanel {
+        open: false,
+        params,
+        selected: 0,
+        status: String::new(),
+    });
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(160.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(TuningText);
+}
+
+fn collect_params(value: &Value, pointer: String, params: &mut Vec<TuningParam>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter() {
+                collect_params(child, format!("{}/{}", pointer, key), params);
+            }
+        }
+        Value::Number(number) if number.is_f64() => {
+            params.push(TuningParam::new(pointer, number.as_f64().unwrap()));
+        }
+        _ => {}
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tuning_controls(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut panel: ResMut<TuningPanel>,
+    mut settings: ResMut<Settings>,
+    mut speed_query: Query<(
+        &mut MovementSpeed,
+        Option<&Player>,
+        Option<&Hare>,
+        Option<&Wolf>,
+        Option<&Deer>,
+    )>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        panel.open = !panel.open;
+    }
+
+    if !panel.open || panel.params.is_empty() {
+        return;
+    }
+
+    let count = panel.params.len();
+
+    if keyboard_input.just_pressed(KeyCode::PageUp) {
+        keyboard_input.reset(KeyCode::PageUp);
+        panel.selected = (panel.selected + count - 1) % count;
+    }
+    if keyboard_input.just_pressed(KeyCode::PageDown) {
+        keyboard_input.reset(KeyCode::PageDown);
+        panel.selected = (panel.selected + 1) % count;
+    }
+
+    let control =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+
+    if control && keyboard_input.just_pressed(KeyCode::S) {
+        keyboard_input.reset(KeyCode::S);
+        panel.status = match settings.save() {
+            Ok(()) => format!("Saved to {}", settings.path),
+            Err(error) => format!("Save failed: {}", error),
+        };
+    }
+
+    let mut change = 0.0;
+
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        keyboard_input.reset(KeyCode::Minus);
+        change -= 1.0;
+    }
+    if keyboard_input.just_pressed(KeyCode::Equals) {
+        keyboard_input.reset(KeyCode::Equals);
+        change += 1.0;
+    }
+
+    if change == 0.0 {
+        return;
+    }
+
+    if keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift) {
+        change *= FINE_STEP;
+    }
+
+    let param = &panel.params[panel.selected];
+    let value = match settings.value.pointer_mut(&param.pointer) {
+        Some(value) => value,
+        None => return,
+    };
+
+    let old = value.as_f64().unwrap_or(0.0);
+    let new = ((old + change * param.step) * PRECISION).round() / PRECISION;
+    *value = Value::from(new);
+
+    if param.pointer.ends_with("/movement_speed") {
+        let species = param.pointer.split('/').nth(1).unwrap_or_default();
+        let delta = (new - old) as f32;
+
+        for (mut speed, player, hare, wolf, deer) in speed_query.iter_mut() {
+            let matches = match species {
+                "player" => player.is_some(),
+                "hare" => hare.is_some(),
+                "wolf" => wolf.is_some(),
+                "deer" => deer.is_some(),
+                _ => false,
+            };
+
+            if matches {
+                speed.value = (speed.value + delta).max(0.0);
+            }
+        }
+    }
+
+    let settings = &settings.value;
+
+    commands.insert_resource(settings::player_data(settings));
+    commands.insert_resource(settings::hare_data(settings));
+    commands.insert_resource(settings::wolf_data(settings));
+    commands.insert_resource(settings::deer_data(settings));
+
+    commands.insert_resource(settings::hare_steering_data(settings));
+    commands.insert_resource(settings::wolf_steering_data(settings));
+    commands.insert_resource(settings::deer_steering_data(settings));
+
+    panel.status.clear();
+}
+
+fn tuning_update(
+    panel: Res<TuningPanel>,
+    settings: Res<Settings>,
+    mut query: Query<(&mut Text, &mut Visible), With<TuningText>>,
+) {
+    for (mut text, mut visible) in query.iter_mut() {
+        visible.is_visible = panel.open;
+
+        if !panel.open {
+            continue;
+        }
+
+        let first = panel
+            .selected
+            .saturating_sub(VISIBLE_PARAMS / 2)
+            .min(panel.params.len().saturating_sub(VISIBLE_PARAMS));
+
+        let mut lines = vec!["Tuning".to_string()];
+
+        for (i, param) in panel
+            .params
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_PARAMS)
+        {
+            let value = settings
+                .value
+                .pointer(&param.pointer)
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+            let marker = if i == panel.selected { ">" } else { " " };
+
+            lines.push(format!("{} {}: {}", marker, param.name(), value));
+        }
+
+        lines.push("PgUp/PgDn - parameter, -/= - adjust (Shift - fine), Ctrl+S - save".to_string());
+
+        if !panel.status.is_empty() {
+            lines.push(panel.status.clone());
+        }
+
+        text.sections[0].value = lines.join("\n");
+    }
+}
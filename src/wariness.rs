@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::{
+    perception::Perception, player::Player, simulation::SIMULATION, utils::dist, TIME_STEP,
+};
+
+pub struct WarinessData {
+    pub gunshot_fear: f32,
+    pub hit_fear: f32,
+    pub distance_fear: f32,
+    pub fear_radius: f32,
+    pub decay: f32,
+    pub retreat_threshold: f32,
+    pub retreat_weight: f32,
+    pub starving_threshold: f32,
+    pub starving_aggression: f32,
+}
+
+pub(crate) struct Wariness {
+    pub fear: f32,
+    pub aggression: f32,
+    pub alerted: bool,
+    pub gunshot_fear: f32,
+    pub hit_fear: f32,
+    pub distance_fear: f32,
+    pub fear_radius: f32,
+    pub decay: f32,
+    pub retreat_threshold: f32,
+}
+
+impl Wariness {
+    pub fn new(data: &WarinessData) -> Self {
+        Self {
+            fear: 0.0,
+            aggression: 0.0,
+            alerted: false,
+            gunshot_fear: data.gunshot_fear,
+            hit_fear: data.hit_fear,
+            distance_fear: data.distance_fear,
+            fear_radius: data.fear_radius,
+            decay: data.decay,
+            retreat_threshold: data.retreat_threshold,
+        }
+    }
+
+    pub fn frighten(&mut self, amount: f32) {
+        self.fear = (self.fear + amount).min(1.0);
+        self.alerted = true;
+    }
+
+    pub fn is_retreating(&self) -> bool {
+        self.fear - self.aggression > self.retreat_threshold
+    }
+}
+
+pub struct WarinessPlugin;
+
+impl Plugin for WarinessPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            SIMULATION,
+            wariness_update
+                .system()
+                .label("wariness")
+                .after("perception"),
+        );
+    }
+}
+
+fn wariness_update(
+    mut query: Query<(&Transform, &Perception, &mut Wariness)>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+) {
+    let player = player_query.single().ok();
+
+    for (transform, perception, mut wariness) in query.iter_mut() {
+        if !perception.gunshots.is_empty() {
+            let fear = wariness.gunshot_fear * perception.gunshots.len() as f32;
+            wariness.frighten(fear);
+        }
+
+        if let Some((player, player_transform)) = player {
+            let ds = dist(transform.translation, player_transform.translation);
+
+            if wariness.alerted
+                && ds < wariness.fear_radius
+                && perception.perceived.contains(&player)
+            {
+                let fear = wariness.distance_fear * (1.0 - ds / wariness.fear_radius) * TIME_STEP;
+                wariness.frighten(fear);
+            }
+        }
+
+        wariness.fear = (wariness.fear - wariness.decay * TIME_STEP).max(0.0);
+    }
+}
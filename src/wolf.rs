@@ -1,21 +1,37 @@
 use std::f32::consts::PI;
 
 use bevy::{
-    core::Time,
     math::{Quat, Vec2, Vec3},
     prelude::{
-        AppBuilder, Commands, Entity, IntoSystem, ParallelSystemDescriptorCoercion, Plugin, Query,
-        Res, ResMut, SpriteBundle, Transform, With,
+        AppBuilder, Commands, Entity, EventReader, EventWriter, IntoSystem,
+        ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SpriteBundle, Transform,
+        Visible, With, Without, World,
     },
-    sprite::collide_aabb::collide,
+    sprite::{collide_aabb::collide, Sprite},
 };
 use rand::Rng;
+use serde_json::{json, Value};
 
 use crate::{
-    components::{Materials, MovementSpeed, Prey, Threat},
-    player::{Bullet, BulletData},
-    steering::{flee, pursue, wander, EvadeWallsData, Physics, PursueData, WanderData},
-    utils::{dist, limit, line_line_intersection},
+    components::{Health, Hidden, Materials, MovementSpeed, Prey, Threat},
+    map::MapLayout,
+    perception::{Perception, PerceptionData},
+    player::{Bullet, BulletData, Player},
+    settings::{get_f32, get_u32},
+    simulation::{SimRng, SimulationClock, SIMULATION},
+    snapshot::{
+        entity_value, get_health, get_perception, get_physics, get_target, get_transform, get_vec3,
+        health_value, load_wariness, perception_value, physics_value, target_value,
+        transform_value, vec3_value, wariness_value, EntityMap,
+    },
+    stats::{BirthEvent, DeathCause, DeathEvent, Species},
+    steering::{
+        flee, pursue, seek, wander, EvadeWallsData, FlankData, FlockingData, Physics, PursueData,
+        SteeringDebug, SteeringKind, WanderData,
+    },
+    targeting::{Target, TargetingData},
+    utils::{dist, limit, set_mag},
+    wariness::{Wariness, WarinessData},
     FieldSize, Walls, TIME_STEP,
 };
 
@@ -25,53 +41,179 @@ pub(crate) struct WolfData {
     pub width: f32,
     pub height: f32,
     pub max_number: u32,
+    pub max_hunger_time: f32,
+    pub health: f32,
+    pub spawn_margin: f32,
+    pub perception: PerceptionData,
+    pub den: DenData,
+    pub wariness: WarinessData,
+}
+
+pub struct DenData {
+    pub size: f32,
+    pub rest_time: f32,
 }
 
 pub struct WolfSteeringData {
     pub wander: WanderData,
     pub evade_walls: EvadeWallsData,
     pub pursue: PursueData,
+    pub pack: PackData,
+    pub targeting: TargetingData,
+    pub territory: TerritoryData,
+}
+
+pub struct PackData {
+    pub size: u32,
+    pub spread: f32,
+    pub cohesion: FlockingData,
+    pub flank: FlankData,
+}
+
+pub struct TerritoryData {
+    pub radius: f32,
+    pub leash_distance: f32,
+    pub patrol_weight: f32,
+    pub return_weight: f32,
+    pub rival_weight: f32,
 }
 
 pub struct WolfBehavior {
     force: Vec3,
     pub hunger_time: f32,
     max_hunger_time: f32,
+    returning: bool,
+    resting_until: f32,
 }
 
+impl WolfBehavior {
+    pub fn feed(&mut self, now: f32) {
+        self.hunger_time = now;
+        self.returning = true;
+    }
+
+    pub fn hunger(&self, now: f32) -> f32 {
+        if self.hunger_time == 0.0 {
+            0.0
+        } else {
+            (now - self.hunger_time) / self.max_hunger_time
+        }
+    }
+
+    pub fn set_hunger(&mut self, now: f32, hunger: f32) {
+        self.hunger_time = now - hunger.max(0.0) * self.max_hunger_time;
+    }
+
+    fn is_resting(&self, now: f32) -> bool {
+        now < self.resting_until
+    }
+}
+
+const MAX_DEN_PLACEMENT_ATTEMPTS: u32 = 16;
+
 pub struct WolfPlugin;
 
 impl Plugin for WolfPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(ActiveWolves { count: 0 })
-            .add_system(wolf_spawn.system().label("wolf_spawn"))
+            .insert_resource(WolfPacks { packs: Vec::new() })
+            .insert_resource(DeadWolves { value: Vec::new() })
+            .add_event::<PackMateShotEvent>()
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_spawn.system().label("wolf_spawn").after("hare_wander"),
+            )
             .add_system(
+                wolf_dens_sync
+                    .system()
+                    .label("wolf_dens_sync")
+                    .after("map_sync"),
+            )
+            .add_system(
+                wolf_dens_visibility
+                    .system()
+                    .label("wolf_dens_visibility")
+                    .after("wolf_dens_sync"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 wolf_wander
                     .system()
                     .label("wolf_wander")
                     .after("wolf_spawn")
                     .before("wolf_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
                 wolf_evade_walls
                     .system()
                     .label("wolf_evade_walls")
                     .before("wolf_move"),
             )
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_wariness
+                    .system()
+                    .label("wolf_wariness")
+                    .after("wariness")
+                    .after("wolf_die")
+                    .before("wolf_pack_target"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_pack_target
+                    .system()
+                    .label("wolf_pack_target")
+                    .after("perception")
+                    .before("wolf_pursue"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_retreat
+                    .system()
+                    .label("wolf_retreat")
+                    .after("wolf_wariness")
+                    .before("wolf_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 wolf_pursue
                     .system()
                     .label("wolf_pursue")
+                    .after("perception")
                     .before("wolf_move"),
             )
-            .add_system(wolf_move.system().label("wolf_move").after("wolf_spawn"))
-            .add_system(
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_territory
+                    .system()
+                    .label("wolf_territory")
+                    .after("wolf_pack_target")
+                    .before("wolf_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_pack_cohesion
+                    .system()
+                    .label("wolf_pack_cohesion")
+                    .after("wolf_pack_target")
+                    .before("wolf_move"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_move.system().label("wolf_move").after("wolf_spawn"),
+            )
+            .add_system_to_stage(
+                SIMULATION,
                 wolf_starve
                     .system()
                     .label("wolf_starve")
                     .after("wolf_spawn"),
             )
-            .add_system(wolf_die.system().label("wolf_die"));
+            .add_system_to_stage(
+                SIMULATION,
+                wolf_die.system().label("wolf_die").after("wolf_starve"),
+            );
     }
 }
 
@@ -79,47 +221,199 @@ struct ActiveWolves {
     count: u32,
 }
 
+struct DeadWolves {
+    value: Vec<Entity>,
+}
+
 pub struct Wolf;
 
+pub(crate) struct PackID {
+    pub value: u32,
+}
+
+pub(crate) struct WolfPack {
+    pub id: u32,
+    pub count: u32,
+    pub target: Target,
+    pub den: Vec3,
+    pub den_entity: Entity,
+}
+
+pub(crate) struct Den;
+
+pub(crate) struct PackMateShotEvent {
+    pub pack: u32,
+}
+
+pub(crate) struct WolfPacks {
+    pub packs: Vec<WolfPack>,
+}
+
 fn wolf_spawn(
     mut commands: Commands,
     materials: Res<Materials>,
     mut active_wolves: ResMut<ActiveWolves>,
+    mut wolf_packs: ResMut<WolfPacks>,
     filed_size: Res<FieldSize>,
     settings: Res<WolfData>,
+    behavior_data: Res<WolfSteeringData>,
+    layout: Res<MapLayout>,
+    mut rng: ResMut<SimRng>,
+    mut births: EventWriter<BirthEvent>,
 ) {
     if active_wolves.count < settings.max_number {
-        let mut rng = rand::thread_rng();
-        let w_span = filed_size.width / 2.0 - 30.0;
-        let h_span = filed_size.height / 2.0 - 30.0;
-        let x = rng.gen_range(-w_span..w_span) as f32;
-        let y = rng.gen_range(-h_span..h_span) as f32;
+        let mut position = Vec3::ZERO;
+
+        if let Some(den) = free_den(&layout, &wolf_packs, settings.den.size) {
+            position = den;
+        } else {
+            for _ in 0..MAX_DEN_PLACEMENT_ATTEMPTS {
+                position = layout.spawn_point(
+                    Species::Wolf,
+                    &filed_size,
+                    settings.spawn_margin,
+                    &mut *rng,
+                );
+
+                if wolf_packs
+                    .packs
+                    .iter()
+                    .all(|pack| dist(pack.den, position) > behavior_data.territory.radius * 2.0)
+                {
+                    break;
+                }
+            }
+        }
+
+        let Vec3 { x, y, .. } = position;
 
-        commands
+        let den_entity = commands
             .spawn_bundle(SpriteBundle {
-                material: materials.wolf_material.clone(),
-                transform: Transform {
-                    translation: Vec3::new(x, y, 0.0),
-                    scale: settings.transform.scale,
-                    ..Default::default()
-                },
+                material: materials.den_material.clone(),
+                sprite: Sprite::new(Vec2::new(settings.den.size, settings.den.size)),
+                transform: Transform::from_xyz(x, y, 0.1),
                 ..Default::default()
             })
-            .insert(Wolf)
-            .insert(Threat)
-            .insert(MovementSpeed::new(settings.movement_speed))
-            .insert(Physics {
-                velocity: Vec3::new(0.0, -2.0, 0.0),
-                acceleration: Vec3::default(),
-                wander_theta: PI / 2.0,
-            })
-            .insert(WolfBehavior {
-                force: Vec3::ZERO,
-                hunger_time: 0.0,
-                max_hunger_time: 10.0,
+            .insert(Den)
+            .id();
+
+        let wolf_count = behavior_data
+            .pack
+            .size
+            .min(settings.max_number - active_wolves.count);
+        let id = rng.gen();
+        wolf_packs.packs.push(WolfPack {
+            id,
+            count: wolf_count,
+            target: Target::default(),
+            den: Vec3::new(x, y, 0.0),
+            den_entity,
+        });
+
+        let spread = behavior_data.pack.spread;
+
+        for _ in 0..wolf_count {
+            let x_offset = rng.gen_range(-spread..spread) as f32;
+            let y_offset = rng.gen_range(-spread..spread) as f32;
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: materials.wolf_material.clone(),
+                    transform: Transform {
+                        translation: Vec3::new(x + x_offset, y + y_offset, 0.0),
+                        scale: settings.transform.scale,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(Wolf)
+                .insert(Threat)
+                .insert(MovementSpeed::new(settings.movement_speed))
+                .insert(Physics {
+                    velocity: Vec3::new(0.0, -2.0, 0.0),
+                    acceleration: Vec3::default(),
+                    wander_theta: PI / 2.0,
+                })
+                .insert(SteeringDebug::default())
+                .insert(WolfBehavior {
+                    force: Vec3::ZERO,
+                    hunger_time: 0.0,
+                    max_hunger_time: settings.max_hunger_time,
+                    returning: false,
+                    resting_until: 0.0,
+                })
+                .insert(PackID { value: id })
+                .insert(Target::default())
+                .insert(Health::new(settings.health))
+                .insert(Perception::new(&settings.perception))
+                .insert(Wariness::new(&settings.wariness));
+
+            births.send(BirthEvent {
+                species: Species::Wolf,
             });
+        }
+
+        active_wolves.count += wolf_count;
+    }
+}
+
+fn free_den(layout: &MapLayout, wolf_packs: &WolfPacks, den_size: f32) -> Option<Vec3> {
+    layout.dens.iter().copied().find(|den| {
+        wolf_packs
+            .packs
+            .iter()
+            .all(|pack| dist(pack.den, *den) > den_size)
+    })
+}
+
+fn wolf_dens_sync(
+    layout: Res<MapLayout>,
+    mut wolf_packs: ResMut<WolfPacks>,
+    mut den_query: Query<&mut Transform, With<Den>>,
+    settings: Res<WolfData>,
+) {
+    if !layout.is_changed() || layout.dens.is_empty() {
+        return;
+    }
+
+    let den_size = settings.den.size;
+
+    for i in 0..wolf_packs.packs.len() {
+        let den = wolf_packs.packs[i].den;
+
+        if layout
+            .dens
+            .iter()
+            .any(|layout_den| dist(*layout_den, den) <= den_size)
+        {
+            continue;
+        }
 
-        active_wolves.count += 1;
+        if let Some(new_den) = free_den(&layout, &wolf_packs, den_size) {
+            let pack = &mut wolf_packs.packs[i];
+            pack.den = new_den;
+
+            if let Ok(mut den_transform) = den_query.get_mut(pack.den_entity) {
+                den_transform.translation.x = new_den.x;
+                den_transform.translation.y = new_den.y;
+            }
+        }
+    }
+}
+
+fn wolf_dens_visibility(
+    layout: Res<MapLayout>,
+    wolf_packs: Res<WolfPacks>,
+    mut den_query: Query<&mut Visible, With<Den>>,
+    settings: Res<WolfData>,
+) {
+    for pack in wolf_packs.packs.iter() {
+        if let Ok(mut visible) = den_query.get_mut(pack.den_entity) {
+            visible.is_visible = !layout
+                .dens
+                .iter()
+                .any(|den| dist(*den, pack.den) <= settings.den.size);
+        }
     }
 }
 
@@ -129,18 +423,30 @@ fn wolf_move(
             &mut Transform,
             &mut Physics,
             &mut WolfBehavior,
+            &mut SteeringDebug,
             &MovementSpeed,
         ),
         With<Wolf>,
     >,
     active_wolves: Res<ActiveWolves>,
     settings: Res<WolfData>,
+    clock: Res<SimulationClock>,
 ) {
     if active_wolves.count < settings.max_number {
         return;
     }
 
-    for (mut transform, mut physics, mut behavior, speed) in query.iter_mut() {
+    let now = clock.elapsed;
+
+    for (mut transform, mut physics, mut behavior, mut steering, speed) in query.iter_mut() {
+        if behavior.is_resting(now) {
+            physics.velocity *= 0.0;
+            physics.acceleration *= 0.0;
+            behavior.force *= 0.0;
+            steering.flush();
+            continue;
+        }
+
         physics.acceleration += behavior.force;
 
         let acc_clone = physics.acceleration.clone();
@@ -149,6 +455,7 @@ fn wolf_move(
         transform.translation += physics.velocity;
         physics.acceleration *= 0.0;
         behavior.force *= 0.0;
+        steering.flush();
 
         let angle = physics.velocity.y.atan2(physics.velocity.x) - PI / 2.0;
 
@@ -157,16 +464,24 @@ fn wolf_move(
 }
 
 fn wolf_wander(
-    mut query: Query<(&Transform, &mut Physics, &mut WolfBehavior), With<Wolf>>,
+    mut query: Query<
+        (
+            &Transform,
+            &mut Physics,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
     active_wolves: Res<ActiveWolves>,
     settings: Res<WolfData>,
     behavior_data: Res<WolfSteeringData>,
+    mut rng: ResMut<SimRng>,
 ) {
     if active_wolves.count < settings.max_number {
         return;
     }
 
-    let mut rng = rand::thread_rng();
     let displace_range: f32 = behavior_data.wander.displace_range;
     let mut displacements = vec![0.0; settings.max_number as usize];
 
@@ -176,7 +491,7 @@ fn wolf_wander(
 
     let mut count: usize = 0;
 
-    for (transform, mut physics, mut behavior) in query.iter_mut() {
+    for (transform, mut physics, mut behavior, mut steering) in query.iter_mut() {
         let force = wander(
             transform.translation,
             physics.velocity,
@@ -185,14 +500,31 @@ fn wolf_wander(
             physics.wander_theta,
             behavior_data.wander.max_force,
         );
-        behavior.force += force * behavior_data.wander.weight;
+        steering.record_wander(
+            transform.translation,
+            physics.velocity,
+            behavior_data.wander.radius,
+            behavior_data.wander.distance,
+            physics.wander_theta,
+        );
+        behavior.force +=
+            steering.record(SteeringKind::Wander, force * behavior_data.wander.weight);
         physics.wander_theta += displacements[count];
         count += 1;
     }
 }
 
 fn wolf_evade_walls(
-    mut wolf_query: Query<(&Transform, &Physics, &MovementSpeed, &mut WolfBehavior), With<Wolf>>,
+    mut wolf_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
     active_wolves: Res<ActiveWolves>,
     settings: Res<WolfData>,
     behavior_data: Res<WolfSteeringData>,
@@ -202,93 +534,518 @@ fn wolf_evade_walls(
         return;
     }
 
-    for (transform, physics, speed, mut behavior) in wolf_query.iter_mut() {
-        for wall in walls.value.iter() {
-            if let Ok(int) = line_line_intersection(
-                wall.point_a,
-                wall.point_b,
+    for (transform, physics, speed, mut behavior, mut steering) in wolf_query.iter_mut() {
+        steering.record_wall_ray(
+            transform.translation,
+            physics.velocity,
+            behavior_data.evade_walls.look_ahead,
+        );
+
+        for hit in walls.ray_hits(
+            transform.translation.truncate(),
+            physics.velocity.truncate(),
+        ) {
+            if hit.distance > behavior_data.evade_walls.look_ahead {
+                continue;
+            }
+
+            let force = flee(
                 transform.translation,
-                transform.translation + physics.velocity,
-            ) {
-                if dist(transform.translation, int) > 40.0 {
+                physics.velocity,
+                hit.point.extend(0.0),
+                speed.value * TIME_STEP,
+            );
+            behavior.force += steering.record(
+                SteeringKind::Walls,
+                force * behavior_data.evade_walls.weight,
+            );
+        }
+    }
+}
+
+fn wolf_wariness(
+    mut wolf_query: Query<(&PackID, &WolfBehavior, &mut Wariness), With<Wolf>>,
+    mut shots: EventReader<PackMateShotEvent>,
+    active_wolves: Res<ActiveWolves>,
+    settings: Res<WolfData>,
+    clock: Res<SimulationClock>,
+) {
+    let shot_packs: Vec<u32> = shots.iter().map(|shot| shot.pack).collect();
+
+    if active_wolves.count < settings.max_number {
+        return;
+    }
+
+    let now = clock.elapsed;
+
+    for (pack_id, behavior, mut wariness) in wolf_query.iter_mut() {
+        for pack in shot_packs.iter() {
+            if *pack == pack_id.value {
+                let fear = wariness.hit_fear;
+                wariness.frighten(fear);
+            }
+        }
+
+        wariness.aggression = if behavior.hunger(now) > settings.wariness.starving_threshold {
+            settings.wariness.starving_aggression
+        } else {
+            0.0
+        };
+    }
+}
+
+fn wolf_retreat(
+    mut wolf_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &Wariness,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
+    player_query: Query<&Transform, With<Player>>,
+    active_wolves: Res<ActiveWolves>,
+    settings: Res<WolfData>,
+) {
+    if active_wolves.count < settings.max_number {
+        return;
+    }
+
+    let player_transform = match player_query.single() {
+        Ok(player_transform) => player_transform,
+        Err(_) => return,
+    };
+
+    for (transform, physics, speed, wariness, mut behavior, mut steering) in wolf_query.iter_mut() {
+        if !wariness.is_retreating()
+            || dist(transform.translation, player_transform.translation) > wariness.fear_radius
+        {
+            continue;
+        }
+
+        let force = flee(
+            transform.translation,
+            physics.velocity,
+            player_transform.translation,
+            speed.value * TIME_STEP,
+        );
+        behavior.force +=
+            steering.record(SteeringKind::Flee, force * settings.wariness.retreat_weight);
+    }
+}
+
+fn wolf_pack_target(
+    member_query: Query<(&Transform, &Perception, &PackID, &Wariness), With<Wolf>>,
+    mut target_query: Query<(&PackID, &mut Target), With<Wolf>>,
+    prey_query: Query<
+        (&Transform, &Physics, Option<&Health>, Option<&Player>),
+        (With<Prey>, Without<Hidden>),
+    >,
+    mut wolf_packs: ResMut<WolfPacks>,
+    active_wolves: Res<ActiveWolves>,
+    settings: Res<WolfData>,
+    behavior_data: Res<WolfSteeringData>,
+    clock: Res<SimulationClock>,
+) {
+    if active_wolves.count < settings.max_number {
+        return;
+    }
+
+    let now = clock.elapsed;
+    let targeting = &behavior_data.targeting;
+    let max_speed = settings.movement_speed * TIME_STEP;
+
+    for pack in wolf_packs.packs.iter_mut() {
+        if pack.target.entity.is_some()
+            && now - pack.target.chase_started > targeting.max_chase_time
+        {
+            pack.target.abandoned = pack.target.entity;
+            pack.target.entity = None;
+        }
+
+        let mut best: Option<(Entity, f32)> = None;
+        let mut current_score: Option<f32> = None;
+        let mut abandoned_seen = false;
+
+        for (wolf_transform, perception, pack_id, wariness) in member_query.iter() {
+            if pack_id.value != pack.id {
+                continue;
+            }
+
+            for prey in perception.perceived.iter() {
+                let (prey_transform, prey_physics, health, player) = match prey_query.get(*prey) {
+                    Ok(prey) => prey,
+                    Err(_) => continue,
+                };
+
+                let ds = dist(wolf_transform.translation, prey_transform.translation);
+                if ds > behavior_data.pursue.radius
+                    || dist(pack.den, prey_transform.translation)
+                        > behavior_data.territory.leash_distance
+                {
+                    continue;
+                }
+
+                if pack.target.abandoned == Some(*prey) {
+                    abandoned_seen = true;
+                    continue;
+                }
+
+                if player.is_some() && wariness.is_retreating() {
                     continue;
                 }
 
-                let force = flee(
-                    transform.translation,
-                    physics.velocity,
-                    int,
-                    speed.value * TIME_STEP,
+                let mut score = targeting.score(
+                    ds,
+                    behavior_data.pursue.radius,
+                    prey_physics.velocity.length() / max_speed,
+                    health.map_or(1.0, |health| health.fraction()),
+                    player.is_some(),
                 );
-                behavior.force += force * behavior_data.evade_walls.weight;
+
+                if player.is_some() {
+                    score += wariness.aggression;
+                }
+
+                if pack.target.entity == Some(*prey) {
+                    current_score = Some(current_score.map_or(score, |current| current.max(score)));
+                }
+
+                if best.map_or(true, |(_, best_score)| score > best_score) {
+                    best = Some((*prey, score));
+                }
+            }
+        }
+
+        if !abandoned_seen {
+            pack.target.abandoned = None;
+        }
+
+        match (current_score, best) {
+            (Some(current), Some((prey, score))) if score > current + targeting.hysteresis => {
+                pack.target.entity = Some(prey);
+                pack.target.score = score;
+                pack.target.chase_started = now;
+            }
+            (Some(current), _) => {
+                pack.target.score = current;
+            }
+            (None, Some((prey, score))) => {
+                pack.target.entity = Some(prey);
+                pack.target.score = score;
+                pack.target.chase_started = now;
+            }
+            (None, None) => {
+                pack.target.entity = None;
+                pack.target.score = 0.0;
             }
         }
     }
+
+    for (pack_id, mut target) in target_query.iter_mut() {
+        *target = wolf_packs
+            .packs
+            .iter()
+            .find(|pack| pack.id == pack_id.value)
+            .map_or(Target::default(), |pack| pack.target);
+    }
 }
 
 fn wolf_pursue(
-    mut wolf_query: Query<(&Transform, &Physics, &MovementSpeed, &mut WolfBehavior), With<Wolf>>,
-    prey_query: Query<(&Transform, &Physics), With<Prey>>,
+    mut wolf_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &PackID,
+            &Target,
+            &Wariness,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
+    member_query: Query<(Entity, &Transform, &PackID), With<Wolf>>,
+    prey_query: Query<(&Transform, &Physics, Option<&Player>), (With<Prey>, Without<Hidden>)>,
     active_wolves: Res<ActiveWolves>,
     settings: Res<WolfData>,
     behavior_data: Res<WolfSteeringData>,
+    clock: Res<SimulationClock>,
 ) {
     if active_wolves.count < settings.max_number {
         return;
     }
 
-    for (wolf_transform, physics, speed, mut behavior) in wolf_query.iter_mut() {
-        for (prey_transform, prey_physics) in prey_query.iter() {
-            let ds = dist(wolf_transform.translation, prey_transform.translation);
+    let now = clock.elapsed;
+
+    for (
+        wolf,
+        wolf_transform,
+        physics,
+        speed,
+        pack_id,
+        target,
+        wariness,
+        mut behavior,
+        mut steering,
+    ) in wolf_query.iter_mut()
+    {
+        if behavior.returning || behavior.is_resting(now) {
+            continue;
+        }
+
+        let target = match target.entity {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let (prey_transform, prey_physics, player) = match prey_query.get(target) {
+            Ok(prey) => prey,
+            Err(_) => continue,
+        };
+
+        if player.is_some() && wariness.is_retreating() {
+            continue;
+        }
+
+        let max_speed = speed.value * TIME_STEP;
+        let ds = dist(wolf_transform.translation, prey_transform.translation);
+
+        let mut slot = 0;
+        let mut members = 0;
+        let mut centroid = Vec3::ZERO;
+
+        for (member, member_transform, member_pack_id) in member_query.iter() {
+            if member_pack_id.value != pack_id.value {
+                continue;
+            }
+
+            if member == wolf {
+                slot = members;
+            }
 
+            centroid += member_transform.translation;
+            members += 1;
+        }
+
+        if members < 2 || ds < behavior_data.pack.flank.radius {
             let force = pursue(
                 wolf_transform.translation,
                 physics.velocity,
                 prey_transform.translation,
                 prey_physics.velocity,
-                speed.value * TIME_STEP,
+                max_speed,
             );
+            behavior.force +=
+                steering.record(SteeringKind::Pursue, force * behavior_data.pursue.weight);
+            continue;
+        }
+
+        centroid /= members as f32;
+
+        let future_position = prey_transform.translation + prey_physics.velocity * (ds / max_speed);
+        let approach = centroid - future_position;
+        let angle = approach.y.atan2(approach.x) + slot as f32 * 2.0 * PI / members as f32;
+        let flank_position = future_position
+            + Vec3::new(angle.cos(), angle.sin(), 0.0) * behavior_data.pack.flank.radius;
+
+        let force = seek(
+            wolf_transform.translation,
+            physics.velocity,
+            flank_position,
+            max_speed,
+        );
+        behavior.force += steering.record(
+            SteeringKind::Pursue,
+            force * behavior_data.pack.flank.weight,
+        );
+    }
+}
+
+fn wolf_territory(
+    mut wolf_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &PackID,
+            &Target,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
+    wolf_packs: Res<WolfPacks>,
+    active_wolves: Res<ActiveWolves>,
+    settings: Res<WolfData>,
+    behavior_data: Res<WolfSteeringData>,
+    clock: Res<SimulationClock>,
+) {
+    if active_wolves.count < settings.max_number {
+        return;
+    }
 
-            behavior.force += if ds > 100.0 {
-                Vec3::ZERO
+    let now = clock.elapsed;
+    let territory = &behavior_data.territory;
+
+    for (transform, physics, speed, pack_id, target, mut behavior, mut steering) in
+        wolf_query.iter_mut()
+    {
+        let pack = match wolf_packs
+            .packs
+            .iter()
+            .find(|pack| pack.id == pack_id.value)
+        {
+            Some(pack) => pack,
+            None => continue,
+        };
+
+        let max_speed = speed.value * TIME_STEP;
+        let ds = dist(transform.translation, pack.den);
+
+        if behavior.returning {
+            if ds < settings.den.size / 2.0 {
+                behavior.returning = false;
+                behavior.resting_until = now + settings.den.rest_time;
+                behavior.hunger_time = now;
             } else {
-                force * behavior_data.pursue.weight
-            };
+                let force = seek(transform.translation, physics.velocity, pack.den, max_speed);
+                behavior.force +=
+                    steering.record(SteeringKind::Seek, force * territory.return_weight);
+            }
+            continue;
+        }
+
+        if target.entity.is_none() && ds > territory.radius {
+            let force = seek(transform.translation, physics.velocity, pack.den, max_speed);
+            behavior.force += steering.record(SteeringKind::Seek, force * territory.patrol_weight);
+        }
+
+        for rival in wolf_packs.packs.iter() {
+            if rival.id == pack.id || dist(transform.translation, rival.den) > territory.radius {
+                continue;
+            }
+
+            let force = flee(
+                transform.translation,
+                physics.velocity,
+                rival.den,
+                max_speed,
+            );
+            behavior.force += steering.record(SteeringKind::Flee, force * territory.rival_weight);
+        }
+    }
+}
+
+fn wolf_pack_cohesion(
+    mut wolf_query: Query<
+        (
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &PackID,
+            &Target,
+            &mut WolfBehavior,
+            &mut SteeringDebug,
+        ),
+        With<Wolf>,
+    >,
+    member_query: Query<(&Transform, &PackID), With<Wolf>>,
+    active_wolves: Res<ActiveWolves>,
+    settings: Res<WolfData>,
+    behavior_data: Res<WolfSteeringData>,
+) {
+    if active_wolves.count < settings.max_number {
+        return;
+    }
+
+    for (transform, physics, speed, pack_id, target, mut behavior, mut steering) in
+        wolf_query.iter_mut()
+    {
+        if target.entity.is_some() {
+            continue;
+        }
+
+        let perception_radius = behavior_data.pack.cohesion.perception_radius;
+        let mut steer = Vec3::default();
+        let mut total = 0.0;
+
+        for (other_transform, other_id) in member_query.iter() {
+            if pack_id.value == other_id.value && other_transform != transform {
+                if dist(transform.translation, other_transform.translation) < perception_radius {
+                    steer += other_transform.translation;
+                    total += 1.0;
+                }
+            }
+        }
+
+        if total > 0.0 {
+            steer /= total;
+            steer -= transform.translation;
+            steer = set_mag(steer, speed.value);
+            steer -= physics.velocity;
+            steer = limit(steer, behavior_data.pack.cohesion.max_force);
+            behavior.force += steering.record(SteeringKind::Cohesion, steer);
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn wolf_starve(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut WolfBehavior), With<Wolf>>,
-    time: Res<Time>,
+    mut query: Query<(Entity, &PackID, &mut WolfBehavior), With<Wolf>>,
+    mut wolf_packs: ResMut<WolfPacks>,
+    mut dead_wolves: ResMut<DeadWolves>,
+    mut deaths: EventWriter<DeathEvent>,
+    clock: Res<SimulationClock>,
     active_wolves: Res<ActiveWolves>,
     settings: Res<WolfData>,
 ) {
+    dead_wolves.value.clear();
+
     if active_wolves.count < settings.max_number {
         return;
     }
 
-    for (wolf, mut behavior) in query.iter_mut() {
-        let now = time.seconds_since_startup();
+    for (wolf, pack_id, mut behavior) in query.iter_mut() {
+        let now = clock.elapsed;
 
         if behavior.hunger_time == 0.0 {
-            behavior.hunger_time = now as f32;
+            behavior.hunger_time = now;
         }
-        if now > (behavior.hunger_time + behavior.max_hunger_time).into() {
+        if now > (behavior.hunger_time + behavior.max_hunger_time) {
             commands.entity(wolf).despawn();
+            dead_wolves.value.push(wolf);
+            leave_pack(&mut commands, &mut wolf_packs, pack_id.value);
+            deaths.send(DeathEvent {
+                species: Species::Wolf,
+                cause: DeathCause::Starved,
+            });
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn wolf_die(
     mut commands: Commands,
-    wolf_query: Query<(&Transform, Entity), With<Wolf>>,
+    mut wolf_query: Query<(&Transform, Entity, &PackID, &mut Health), With<Wolf>>,
     bullet_query: Query<(Entity, &Transform), With<Bullet>>,
+    mut wolf_packs: ResMut<WolfPacks>,
+    dead_wolves: Res<DeadWolves>,
+    mut shots: EventWriter<PackMateShotEvent>,
+    mut deaths: EventWriter<DeathEvent>,
     wolf_data: Res<WolfData>,
     bullet_data: Res<BulletData>,
 ) {
-    for (wolf_transform, wolf) in wolf_query.iter() {
+    for (wolf_transform, wolf, pack_id, mut health) in wolf_query.iter_mut() {
+        if dead_wolves.value.contains(&wolf) {
+            continue;
+        }
+
         for (bullet, bullet_transform) in bullet_query.iter() {
             if collide(
                 wolf_transform.translation,
@@ -298,9 +1055,187 @@ fn wolf_die(
             )
             .is_some()
             {
-                commands.entity(wolf).despawn();
                 commands.entity(bullet).despawn();
+                health.value -= bullet_data.damage;
+                shots.send(PackMateShotEvent {
+                    pack: pack_id.value,
+                });
+
+                if health.value <= 0.0 {
+                    commands.entity(wolf).despawn();
+                    leave_pack(&mut commands, &mut wolf_packs, pack_id.value);
+                    deaths.send(DeathEvent {
+                        species: Species::Wolf,
+                        cause: DeathCause::Shot,
+                    });
+                    break;
+                }
             }
         }
     }
 }
+
+fn leave_pack(commands: &mut Commands, wolf_packs: &mut WolfPacks, id: u32) {
+    if let Some(pack) = wolf_packs.packs.iter_mut().find(|pack| pack.id == id) {
+        pack.count = pack.count.saturating_sub(1);
+
+        if pack.count == 0 {
+            commands.entity(pack.den_entity).despawn();
+        }
+    }
+
+    wolf_packs.packs.retain(|pack| pack.count > 0);
+}
+
+pub(crate) fn save_snapshot(world: &mut World) -> Value {
+    let wolves: Vec<Value> = world
+        .query_filtered::<(
+            Entity,
+            &Transform,
+            &Physics,
+            &MovementSpeed,
+            &WolfBehavior,
+            &PackID,
+            &Target,
+            &Health,
+            &Perception,
+            &Wariness,
+        ), With<Wolf>>()
+        .iter(world)
+        .map(
+            |(
+                wolf,
+                transform,
+                physics,
+                speed,
+                behavior,
+                pack,
+                target,
+                health,
+                perception,
+                wariness,
+            )| {
+                json!({
+                    "id": wolf.id(),
+                    "transform": transform_value(transform),
+                    "physics": physics_value(physics),
+                    "movement_speed": speed.value,
+                    "behavior": {
+                        "force": vec3_value(behavior.force),
+                        "hunger_time": behavior.hunger_time,
+                        "max_hunger_time": behavior.max_hunger_time,
+                        "returning": behavior.returning,
+                        "resting_until": behavior.resting_until,
+                    },
+                    "pack": pack.value,
+                    "target": target_value(target),
+                    "health": health_value(health),
+                    "perception": perception_value(perception),
+                    "wariness": wariness_value(wariness),
+                })
+            },
+        )
+        .collect();
+
+    let dens: Vec<Value> = world
+        .query_filtered::<(Entity, &Transform), With<Den>>()
+        .iter(world)
+        .map(|(den, transform)| {
+            json!({
+                "id": den.id(),
+                "transform": transform_value(transform),
+            })
+        })
+        .collect();
+
+    let packs: Vec<Value> = world
+        .get_resource::<WolfPacks>()
+        .unwrap()
+        .packs
+        .iter()
+        .map(|pack| {
+            json!({
+                "pack": pack.id,
+                "count": pack.count,
+                "target": target_value(&pack.target),
+                "den": vec3_value(pack.den),
+                "den_entity": entity_value(Some(pack.den_entity)),
+            })
+        })
+        .collect();
+
+    json!({
+        "active": world.get_resource::<ActiveWolves>().unwrap().count,
+        "entities": wolves,
+        "dens": dens,
+        "packs": packs,
+    })
+}
+
+pub(crate) fn load_snapshot(world: &mut World, snapshot: &Value, entities: &EntityMap) {
+    let materials = world.get_resource::<Materials>().unwrap();
+    let wolf_material = materials.wolf_material.clone();
+    let den_material = materials.den_material.clone();
+    let den_size = world.get_resource::<WolfData>().unwrap().den.size;
+
+    world.get_resource_mut::<ActiveWolves>().unwrap().count = get_u32(&snapshot["active"], 0);
+    world.get_resource_mut::<WolfPacks>().unwrap().packs = snapshot["packs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|pack| {
+            Some(WolfPack {
+                id: get_u32(&pack["pack"], 0),
+                count: get_u32(&pack["count"], 0),
+                target: get_target(&pack["target"], entities),
+                den: get_vec3(&pack["den"]),
+                den_entity: entities.get(&pack["den_entity"])?,
+            })
+        })
+        .collect();
+
+    for (den, record) in entities.records(&snapshot["dens"]) {
+        world
+            .entity_mut(den)
+            .insert_bundle(SpriteBundle {
+                material: den_material.clone(),
+                sprite: Sprite::new(Vec2::new(den_size, den_size)),
+                transform: get_transform(&record["transform"]),
+                ..Default::default()
+            })
+            .insert(Den);
+    }
+
+    for (wolf, record) in entities.records(&snapshot["entities"]) {
+        let behavior = &record["behavior"];
+        let mut wariness = Wariness::new(&world.get_resource::<WolfData>().unwrap().wariness);
+        load_wariness(&record["wariness"], &mut wariness);
+
+        world
+            .entity_mut(wolf)
+            .insert_bundle(SpriteBundle {
+                material: wolf_material.clone(),
+                transform: get_transform(&record["transform"]),
+                ..Default::default()
+            })
+            .insert(Wolf)
+            .insert(Threat)
+            .insert(MovementSpeed::new(get_f32(&record["movement_speed"], 0.0)))
+            .insert(get_physics(&record["physics"]))
+            .insert(SteeringDebug::default())
+            .insert(WolfBehavior {
+                force: get_vec3(&behavior["force"]),
+                hunger_time: get_f32(&behavior["hunger_time"], 0.0),
+                max_hunger_time: get_f32(&behavior["max_hunger_time"], 1.0),
+                returning: behavior["returning"].as_bool().unwrap_or(false),
+                resting_until: get_f32(&behavior["resting_until"], 0.0),
+            })
+            .insert(PackID {
+                value: get_u32(&record["pack"], 0),
+            })
+            .insert(get_target(&record["target"], entities))
+            .insert(get_health(&record["health"]))
+            .insert(get_perception(&record["perception"], entities))
+            .insert(wariness);
+    }
+}
@@ -0,0 +1,127 @@
+use hunter_game::{positions, species_app, step, write_json, Settings, Species};
+use serde_json::{from_str, json, Value};
+use std::{env, fs, path::PathBuf};
+
+const SEED: u64 = 7;
+const TICKS: u32 = 600;
+const SAMPLE_INTERVAL: u32 = 20;
+const TOLERANCE: f64 = 0.01;
+const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+const SETTINGS_NAME: &str = "settings";
+
+fn trajectories(species: Species) -> Value {
+    let settings = Settings::load(golden_path(SETTINGS_NAME).to_str().unwrap());
+    let mut app = species_app(settings, SEED, species);
+    let mut samples = Vec::new();
+
+    for tick in 1..=TICKS {
+        step(&mut app);
+
+        if tick % SAMPLE_INTERVAL != 0 {
+            continue;
+        }
+
+        let positions: Vec<Value> = positions(&mut app, species)
+            .iter()
+            .map(|position| json!([round(position.x), round(position.y)]))
+            .collect();
+
+        samples.push(json!({ "tick": tick, "positions": positions }));
+    }
+
+    json!({
+        "seed": SEED,
+        "ticks": TICKS,
+        "samples": samples,
+    })
+}
+
+fn round(value: f32) -> f64 {
+    (value as f64 * 1000.0).round() / 1000.0
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name))
+}
+
+fn check_golden(name: &str, actual: Value) {
+    let path = golden_path(name);
+
+    let hint = format!("rerun with {}=1 to accept the new trajectories", UPDATE_VAR);
+
+    if env::var(UPDATE_VAR).is_ok() {
+        write_json(path.to_str().unwrap(), &actual).unwrap();
+        eprintln!("Recorded golden trajectories in {}", path.display());
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "{}: {} is missing; {}",
+        name,
+        path.display(),
+        hint
+    );
+
+    let expected: Value = from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+    let expected_samples = expected["samples"].as_array().unwrap();
+    let actual_samples = actual["samples"].as_array().unwrap();
+    assert_eq!(
+        expected_samples.len(),
+        actual_samples.len(),
+        "{}: sample count differs; {}",
+        name,
+        hint
+    );
+
+    for (expected, actual) in expected_samples.iter().zip(actual_samples.iter()) {
+        let tick = &actual["tick"];
+        let expected = expected["positions"].as_array().unwrap();
+        let actual = actual["positions"].as_array().unwrap();
+        assert_eq!(
+            expected.len(),
+            actual.len(),
+            "{}: entity count differs at tick {}; {}",
+            name,
+            tick,
+            hint
+        );
+
+        for (i, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+            for axis in 0..2 {
+                let expected = expected[axis].as_f64().unwrap();
+                let actual = actual[axis].as_f64().unwrap();
+
+                assert!(
+                    (expected - actual).abs() <= TOLERANCE,
+                    "{}: entity {} at tick {} moved from {} to {}; {}",
+                    name,
+                    i,
+                    tick,
+                    expected,
+                    actual,
+                    hint
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn hare_trajectories_match_golden() {
+    check_golden("hare", trajectories(Species::Hare));
+}
+
+#[test]
+fn wolf_trajectories_match_golden() {
+    check_golden("wolf", trajectories(Species::Wolf));
+}
+
+#[test]
+fn deer_trajectories_match_golden() {
+    check_golden("deer", trajectories(Species::Deer));
+}